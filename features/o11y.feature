@@ -4,5 +4,294 @@ Feature: OpenTelemetry metrics
     Given Worker is configured to send metrics to a mock OpenTelemetry collector
     When  Worker is triggered
     Then  Worker metrics are published
-    And   Metric name should include "cloudflare_worker"
-    And   Metric name should include "cloudflare_worker_cpu"
+    And   Metric name should include "cloudflare_worker_requests"
+    And   Metric name should include "cloudflare_worker_cpu_time"
+
+  Scenario: Metrics from healthy collectors published when another collector fails
+    Given Worker is configured to point to mock Cloudflare API
+    Given Cloudflare API returns errors for "queueMessageOperationsAdaptiveGroups"
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metric name should include "cloudflare_worker_requests"
+    And   Metric name should include "cloudflare_exporter_collector_success"
+
+  Scenario: Sums published with delta temporality
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured with "OTLP_TEMPORALITY" set to "delta"
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Sum metrics should have "delta" temporality
+
+  Scenario: Sums keep a running total across runs
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered for 2 consecutive minutes
+    Then  Worker metrics are published
+    And   Sum metrics should have "cumulative" temporality
+    And   Sum "cloudflare_worker_requests" for "script_name" set to "log-forwarder-worker-dev" should have a running total of 8
+    And   Sum "cloudflare_worker_requests" for "script_name" set to "log-forwarder-worker-dev" should keep the start time of the first run
+
+  Scenario: Datasets larger than a page fetched over several pages
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured with "GRAPHQL_PAGE_SIZE" set to "2"
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Cloudflare API should have received 3 queries for "workersInvocationsAdaptive("
+    And   Sum "cloudflare_worker_requests" should have a total of 26
+    And   Gauge "cloudflare_exporter_truncated_results" for collector "workers" should have value 0
+
+  Scenario: Datasets with more pages than allowed reported as truncated
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured with "GRAPHQL_PAGE_SIZE" set to "2"
+    Given Worker is configured with "GRAPHQL_MAX_PAGES" set to "1"
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Cloudflare API should have received 1 queries for "workersInvocationsAdaptive("
+    And   Sum "cloudflare_worker_requests" should have a total of 5
+    And   Gauge "cloudflare_exporter_truncated_results" for collector "workers" should have value 1
+
+  Scenario: Complete minutes queried and exported as their own data points
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Cloudflare API should have been queried for a complete minute ending at least 3 minutes ago
+    And   Sum "cloudflare_worker_requests" should have data points for 2 minutes
+
+  Scenario: Quantiles published as summaries
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metric "cloudflare_worker_cpu_time" should be a summary with quantile 0.999
+
+  Scenario: Metric names and units are stable
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metrics should have the following names and units:
+      | name                                          | unit        |
+      | cloudflare_worker_requests                    | {request}   |
+      | cloudflare_worker_errors                      | {error}     |
+      | cloudflare_worker_cpu_time                    | us          |
+      | cloudflare_worker_duration                    | GB.s        |
+      | cloudflare_worker_subrequests                 | {request}   |
+      | cloudflare_worker_wall_time                   | us          |
+      | cloudflare_worker_response_body_size          | By          |
+      | cloudflare_d1_read_queries                    | {query}     |
+      | cloudflare_d1_write_queries                   | {query}     |
+      | cloudflare_d1_rows_read                       | {row}       |
+      | cloudflare_d1_rows_written                    | {row}       |
+      | cloudflare_d1_query_batch_response_size       | By          |
+      | cloudflare_d1_query_batch_time                | ms          |
+      | cloudflare_durable_objects_requests           | {request}   |
+      | cloudflare_durable_objects_errors             | {error}     |
+      | cloudflare_durable_objects_response_body_size | By          |
+      | cloudflare_durable_objects_wall_time          | us          |
+      | cloudflare_queue_backlog_bytes                | By          |
+      | cloudflare_queue_backlog_messages             | {message}   |
+      | cloudflare_queue_consumer_concurrency         | {consumer}  |
+      | cloudflare_queue_operations_billable          | {operation} |
+      | cloudflare_queue_operations_lag_time          | ms          |
+      | cloudflare_queue_operations_retry_count       | {retry}     |
+      | cloudflare_exporter_collector_success         | 1           |
+      | cloudflare_exporter_truncated_results         | 1           |
+
+  Scenario: Resource attributes published
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured with "OTEL_RESOURCE_ATTRIBUTES" set to "deployment.environment=test,team=o11y%20team"
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Resource attribute "cloud.provider" should be "cloudflare"
+    And   Resource attribute "cloud.account.id" should be "1234"
+    And   Resource attribute "service.name" should be "cloudflare-otlp-exporter"
+    And   Resource attribute "deployment.environment" should be "test"
+    And   Resource attribute "team" should be "o11y team"
+
+  Scenario: Metrics published for multiple accounts
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured with "CLOUDFLARE_ACCOUNT_ID" set to "1234,5678"
+    Given Cloudflare API returns errors for "5678"
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metrics should be published for account "1234"
+    And   Metrics should be published for account "5678"
+
+  Scenario: Accounts discovered when no account is configured
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to discover accounts
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metrics should be published for account "1234"
+    And   Metrics should be published for account "5678"
+
+  Scenario: Discovered accounts filtered by name
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to discover accounts
+    Given Worker is configured with "ACCOUNT_NAME_DENY" set to "sandbox*"
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metrics should be published for account "1234"
+    And   Metrics should not be published for account "5678"
+
+  Scenario: Only the zones of the configured accounts listed
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Cloudflare API should have received 0 requests for "/accounts"
+    And   Cloudflare API should have received 1 requests for "/zones?page=1&per_page=50&account.id=1234"
+
+  Scenario: Zones not discovered without the EXPORTER_STATE KV namespace
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured without the EXPORTER_STATE KV namespace
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metrics should be published for account "1234"
+    And   Cloudflare API should have received 0 requests for "/zones"
+
+  Scenario: Zone metrics published for discovered zones
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metrics should have the following names and units:
+      | name                         | unit      |
+      | cloudflare_zone_requests     | {request} |
+      | cloudflare_zone_bytes        | By        |
+      | cloudflare_zone_cached_bytes | By        |
+
+  Scenario: Zones of an account queried together
+    Given Worker is configured to point to mock Cloudflare API
+    Given Cloudflare API lists 3 zones
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Cloudflare API should have received 1 queries for "httpRequestsAdaptiveGroups"
+    And   Sum "cloudflare_zone_requests" should have a total of 456
+    And   Sum "cloudflare_zone_requests" should have attribute "zone_name" set to "example.com"
+    And   Sum "cloudflare_zone_requests" should have attribute "zone_name" set to "example-2.com"
+    And   Sum "cloudflare_zone_requests" should have attribute "zone_name" set to "example-3.com"
+
+  Scenario: Zones paginated on their own once their page is full
+    Given Worker is configured to point to mock Cloudflare API
+    Given Cloudflare API lists 3 zones
+    Given Worker is configured with "GRAPHQL_PAGE_SIZE" set to "1"
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Cloudflare API should have received 10 queries for "httpRequestsAdaptiveGroups"
+    And   Sum "cloudflare_zone_requests" should have a total of 456
+    And   Gauge "cloudflare_exporter_truncated_results" for collector "zone_http_requests" should have value 0
+
+  Scenario: Cache metrics published for discovered zones
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metric name should include "cloudflare_zone_cache_requests"
+    And   Metric name should include "cloudflare_zone_cache_bytes"
+    And   Gauge "cloudflare_zone_cache_hit_ratio" should have value 0.7894736842105263
+
+  Scenario: Origin metrics published for discovered zones
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metric name should include "cloudflare_zone_origin_requests"
+    And   Metric "cloudflare_zone_origin_response_duration" should be a summary with quantile 0.99
+
+  Scenario: Firewall event metrics published for discovered zones
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metrics should have the following names and units:
+      | name                            | unit    |
+      | cloudflare_zone_firewall_events | {event} |
+
+  Scenario: Firewall events published as logs
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    Given Worker is configured to send logs to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Log records should include attribute "ray_id" set to "88c2b6c4e9a1f00d"
+    And   Log records should include attribute "rule_id" set to "6179ae15870a4bb7b2d480d4843b323c"
+
+  Scenario: Firewall events of the same ray fetched over several pages
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured with "GRAPHQL_PAGE_SIZE" set to "1"
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    Given Worker is configured to send logs to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   2 log records should have attribute "ray_id" set to "88c2b6c4e9a1f00d"
+    And   Log records should include attribute "rule_id" set to "e3a1c7f0b5d24d6b9f8a2c4e6b0d1f37"
+
+  Scenario: DNS metrics published for discovered zones
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metrics should have the following names and units:
+      | name                                | unit    |
+      | cloudflare_zone_dns_queries         | {query} |
+      | cloudflare_zone_dns_processing_time | us      |
+    And   Metric "cloudflare_zone_dns_processing_time" should be a summary with quantile 0.9
+
+  Scenario: Load balancer metrics published for discovered zones
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metrics should have the following names and units:
+      | name                                        | unit      |
+      | cloudflare_zone_load_balancer_requests      | {request} |
+      | cloudflare_zone_health_checks               | {check}   |
+      | cloudflare_zone_health_check_status_changes | {change}  |
+      | cloudflare_zone_health_check_rtt            | ms        |
+
+  Scenario: Worker metrics broken down by status and version
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Sum "cloudflare_worker_errors" should have attribute "status" set to "exceededCpu"
+    And   Sum "cloudflare_worker_requests" should have attribute "script_version" set to "9b2c4f8e-1c3a-4d57-a1f0-6f3e2b8d7c10"
+    And   Sum "cloudflare_worker_requests" should not have attribute "dispatch_namespace_name"
+
+  Scenario: Worker cron trigger metrics published
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metrics should have the following names and units:
+      | name                              | unit        |
+      | cloudflare_worker_cron_executions | {execution} |
+      | cloudflare_worker_cron_cpu_time   | us          |
+    And   Sum "cloudflare_worker_cron_executions" should have attribute "cron" set to "0 * * * *"
+    And   Sum "cloudflare_worker_cron_executions" should have attribute "status" set to "exceededCpu"
+    And   Cloudflare API queries should set "afterScheduledDatetime" to a time
+
+  Scenario: Queue consumer concurrency published
+    Given Worker is configured to point to mock Cloudflare API
+    Given Worker is configured to send metrics to a mock OpenTelemetry collector
+    When  Worker is triggered
+    Then  Worker metrics are published
+    And   Metrics should have the following names and units:
+      | name                                      | unit       |
+      | cloudflare_queue_consumer_concurrency     | {consumer} |
+      | cloudflare_queue_consumer_sample_interval | 1          |
+    And   Gauge "cloudflare_queue_consumer_concurrency" should have value 3.5
+    And   Gauge "cloudflare_queue_consumer_sample_interval" should have value 1
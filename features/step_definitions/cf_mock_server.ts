@@ -4,6 +4,10 @@ import fs from "fs";
 
 export class CloudflareMockServer {
     server: http.Server | undefined;
+    failingDatasets: Set<string> = new Set<string>();
+    queries: string[] = [];
+    restRequests: string[] = [];
+    zoneCount = 1;
 
     start() {
         let self = this;
@@ -11,6 +15,18 @@ export class CloudflareMockServer {
         const d1Query = fs.readFileSync('./features/data/d1_query_response.json').toString();
         const durableObjectsQuery = fs.readFileSync('./features/data/durableobjects_query_response.json').toString();
         const queueBacklogQuery = fs.readFileSync('./features/data/queue_backlog_query_response.json').toString();
+        const zoneHttpRequestsQuery = fs.readFileSync('./features/data/zone_http_requests_query_response.json').toString();
+        const zoneOriginQuery = fs.readFileSync('./features/data/zone_origin_query_response.json').toString();
+        const zoneFirewallEventsQuery = fs.readFileSync('./features/data/zone_firewall_events_query_response.json').toString();
+        const zoneFirewallEventLogsQuery = fs.readFileSync('./features/data/zone_firewall_event_logs_query_response.json').toString();
+        const zoneDnsQuery = fs.readFileSync('./features/data/zone_dns_query_response.json').toString();
+        const zoneLoadBalancingQuery = fs.readFileSync('./features/data/zone_load_balancing_query_response.json').toString();
+        const zoneHealthChecksQuery = fs.readFileSync('./features/data/zone_health_checks_query_response.json').toString();
+        const workerCronQuery = fs.readFileSync('./features/data/worker_cron_query_response.json').toString();
+        const queueConsumerQuery = fs.readFileSync('./features/data/queue_consumer_query_response.json').toString();
+        const queueOperationsQuery = fs.readFileSync('./features/data/queue_operations_query_response.json').toString();
+        const accountsList = fs.readFileSync('./features/data/accounts_response.json').toString();
+        const zonesList = fs.readFileSync('./features/data/zones_response.json').toString();
         this.server = http.createServer((req, res) => {
             var body = "";
             req.on('readable', function() {
@@ -23,16 +39,53 @@ export class CloudflareMockServer {
             req.on('end', function() {
                 res.statusCode = 200;
                 res.setHeader('Content-Type', 'application/json');
+                if (req.method === 'GET') {
+                    self.restRequests.push(req.url ?? '');
+                }
+                if (req.method === 'GET' && req.url?.startsWith('/accounts')) {
+                    res.end(accountsList);
+                    return;
+                }
+                if (req.method === 'GET' && req.url?.startsWith('/zones')) {
+                    res.end(self.listZones(zonesList));
+                    return;
+                }
+                for (let dataset of self.failingDatasets) {
+                    if (body.indexOf(dataset) > -1) {
+                        res.end("{\"data\":null,\"errors\":[{\"message\":\"not authorized for that account\"}]}");
+                        return;
+                    }
+                }
+                self.queries.push(body);
+                const respond = (response: string) => res.end(CloudflareMockServer.paginate(response, body));
                 if (body.indexOf('d1AnalyticsAdaptiveGroups') > -1) {
-                    res.end(d1Query);
+                    respond(d1Query);
                 } else if (body.indexOf('durableObjectsInvocationsAdaptiveGroups') > -1) {
-                    res.end(durableObjectsQuery);
+                    respond(durableObjectsQuery);
+                } else if (body.indexOf('workersInvocationsScheduled') > -1) {
+                    respond(workerCronQuery);
+                } else if (body.indexOf('queueConsumerMetricsAdaptiveGroups') > -1) {
+                    respond(queueConsumerQuery);
                 } else if (body.indexOf('queueBacklogAdaptiveGroups') > -1) {
-                    res.end(queueBacklogQuery);
+                    respond(queueBacklogQuery);
+                } else if (body.indexOf('loadBalancingRequestsAdaptiveGroups') > -1) {
+                    respond(zoneLoadBalancingQuery);
+                } else if (body.indexOf('healthCheckEventsAdaptiveGroups') > -1) {
+                    respond(zoneHealthChecksQuery);
+                } else if (body.indexOf('dnsAnalyticsAdaptiveGroups') > -1) {
+                    respond(zoneDnsQuery);
+                } else if (body.indexOf('firewallEventsAdaptive(') > -1) {
+                    respond(zoneFirewallEventLogsQuery);
+                } else if (body.indexOf('firewallEventsAdaptiveGroups') > -1) {
+                    respond(zoneFirewallEventsQuery);
+                } else if (body.indexOf('originResponseDurationMs') > -1) {
+                    respond(zoneOriginQuery);
+                } else if (body.indexOf('httpRequestsAdaptiveGroups') > -1) {
+                    respond(zoneHttpRequestsQuery);
                 } else if (body.indexOf('queueMessageOperationsAdaptiveGroups') > -1) {
-                    res.end("{\"data\":{\"viewer\":{\"accounts\":[{\"queueMessageOperationsAdaptiveGroups\":[]}]}},\"errors\":null}");
+                    respond(queueOperationsQuery);
                 } else {
-                    res.end(workerQuery);
+                    respond(workerQuery);
                 }
             });
         });
@@ -41,6 +94,85 @@ export class CloudflareMockServer {
         });
     }
 
+    /**
+     * Lists `zoneCount` zones, copies of the first zone of the fixture with their own id and name.
+     */
+    listZones(response: string): string {
+        const parsed = JSON.parse(response);
+        const zone = parsed.result[0];
+        parsed.result = [...Array(this.zoneCount).keys()].map((i) => i === 0 ? zone : {
+            ...zone,
+            id: `${zone.id}-${i + 1}`,
+            name: zone.name.replace('.', `-${i + 1}.`),
+        });
+        return JSON.stringify(parsed);
+    }
+
+    /**
+     * Honours the limit and the keyset filter of the query, as the Cloudflare API would: the rows of each dataset
+     * are sorted by (datetime, dimensions...) and only the first `limit` rows after the cursor are returned.
+     *
+     * The fixtures are shifted so that their first minute is the start of the queried window. Zone queries get
+     * one copy of the fixture zone per zone in `zoneTags`, each paginated on its own.
+     */
+    static paginate(response: string, body: string): string {
+        const variables = JSON.parse(body).variables;
+        const parsed = JSON.parse(response);
+        const viewer = parsed.data?.viewer;
+        if (variables === undefined || viewer === undefined || viewer === null) {
+            return response;
+        }
+        if (variables.zoneTags !== undefined && viewer.zones?.length > 0) {
+            const zone = JSON.stringify(viewer.zones[0]);
+            viewer.zones = variables.zoneTags.map((zoneTag: string) => ({...JSON.parse(zone), zoneTag}));
+        }
+        const afterNames = Object.keys(variables).filter((name) => name.startsWith('after') && name !== 'afterDatetime');
+        const fields = afterNames.map((name) => name.charAt(5).toLowerCase() + name.slice(6));
+        const datetimeOf = (row: any) => new Date(row.dimensions?.datetimeMinute ?? row.datetime).getTime();
+        const valueOf = (row: any, field: string) => row.dimensions?.[field] ?? row[field];
+        const keyOf = (row: any) => [datetimeOf(row), ...fields.map((field) => valueOf(row, field))];
+        const after = [new Date(variables.afterDatetime).getTime(), ...afterNames.map((name) => variables[name])];
+        const compare = (a: any[], b: any[]) => {
+            for (let i = 0; i < a.length; i++) {
+                if (a[i] < b[i]) return -1;
+                if (a[i] > b[i]) return 1;
+            }
+            return 0;
+        };
+
+        for (let node of [...(viewer.accounts ?? []), ...(viewer.zones ?? [])]) {
+            for (let dataset of Object.keys(node)) {
+                if (!Array.isArray(node[dataset]) || node[dataset].length === 0) {
+                    continue;
+                }
+                const rows: any[] = node[dataset];
+                const shift = new Date(variables.datetimeStart).getTime() - Math.min(...rows.map(datetimeOf));
+                const shifted = (datetime: string) => new Date(new Date(datetime).getTime() + shift).toISOString().replace('.000Z', 'Z');
+                for (let row of rows) {
+                    if (row.dimensions?.datetimeMinute !== undefined) {
+                        row.dimensions.datetimeMinute = shifted(row.dimensions.datetimeMinute);
+                    }
+                    if (row.datetime !== undefined) {
+                        row.datetime = shifted(row.datetime);
+                    }
+                }
+                node[dataset] = rows
+                    .filter((row) => compare(keyOf(row), after) > 0)
+                    .sort((a, b) => compare(keyOf(a), keyOf(b)))
+                    .slice(0, variables.limit);
+            }
+        }
+        return JSON.stringify(parsed);
+    }
+
+    queryVariables() {
+        return this.queries.map((query) => JSON.parse(query).variables);
+    }
+
+    queryCount(dataset: string) {
+        return this.queries.filter((query) => query.indexOf(dataset) > -1).length;
+    }
+
     url() {
         const { port } = this.server?.address() as AddressInfo;
         return `http://localhost:${port}/`;
@@ -51,5 +183,9 @@ export class CloudflareMockServer {
             this.server.close();
             this.server = undefined;
         }
+        this.failingDatasets.clear();
+        this.queries = [];
+        this.restRequests = [];
+        this.zoneCount = 1;
     }
 }
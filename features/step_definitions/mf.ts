@@ -1,9 +1,15 @@
 import {Log, LogLevel, Miniflare} from "miniflare";
 import { MockAgent } from "undici";
+import fs from "fs";
+import os from "os";
+import path from "path";
 
 type MfConfig = {
     metricsUrl: string|undefined;
-    cloudflareApiUrl: string|undefined
+    cloudflareApiUrl: string|undefined;
+    durableObjectsPersist: string|boolean;
+    stateKvNamespace: boolean;
+    vars: Record<string, string>;
 };
 
 export class MiniflareDriver {
@@ -12,6 +18,9 @@ export class MiniflareDriver {
     config: MfConfig = {
         metricsUrl: undefined,
         cloudflareApiUrl: undefined,
+        durableObjectsPersist: false,
+        stateKvNamespace: true,
+        vars: {},
     }
 
     start(options?: {metricsUrl?: string, cloudflareApiUrl?: string}): Miniflare {
@@ -50,6 +59,7 @@ export class MiniflareDriver {
             log: new Log(LogLevel.DEBUG), // Enable debug messages
             cachePersist: false,
             d1Persist: false,
+            durableObjectsPersist: self.config.durableObjectsPersist,
             kvPersist: false,
             r2Persist: false,
             workers: [{
@@ -60,10 +70,16 @@ export class MiniflareDriver {
                 bindings: {
                     METRICS_URL: self.config.metricsUrl,
                     CLOUDFLARE_API_URL: self.config.cloudflareApiUrl,
+                    CLOUDFLARE_REST_API_URL: self.config.cloudflareApiUrl,
                     CLOUDFLARE_API_KEY: "fake-key",
                     CLOUDFLARE_ACCOUNT_ID: "1234",
                     OTLP_ENCODING: "json",
+                    ...self.config.vars,
                 },
+                durableObjects: {
+                    CUMULATIVE_SUMS: "CumulativeSums",
+                },
+                kvNamespaces: self.config.stateKvNamespace ? ["EXPORTER_STATE"] : [],
                 modulesRules: [
                     { type: "CompiledWasm", include: ["**/*.wasm"], fallthrough: true },
                 ],
@@ -73,6 +89,9 @@ export class MiniflareDriver {
     }
 
     dispose() {
+        this.config.vars = {};
+        this.config.durableObjectsPersist = false;
+        this.config.stateKvNamespace = true;
         if (this.mf === undefined) {
             return;
         }
@@ -89,4 +108,18 @@ export class MiniflareDriver {
         console.log("Triggered worker");
         this.dispose();
     }
+
+    async triggerForConsecutiveMinutes(minutes: number) {
+        // Keep the Durable Object storage across runs, each run querying the minute after the previous one
+        let persist = fs.mkdtempSync(path.join(os.tmpdir(), "cf-o11y-worker-"));
+        let vars = this.config.vars;
+        for (let minute = minutes; minute >= 1; minute--) {
+            this.config.durableObjectsPersist = persist;
+            this.config.vars = {...vars, INGESTION_LAG_MINUTES: String(minute + 2)};
+            this.start({});
+            await this.mf?.dispatchFetch("http://localhost:8787/");
+            console.log("Triggered worker");
+            await this.dispose();
+        }
+    }
 }
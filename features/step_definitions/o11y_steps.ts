@@ -1,4 +1,4 @@
-import {After, Given, When, Then} from '@cucumber/cucumber';
+import {After, DataTable, Given, When, Then} from '@cucumber/cucumber';
 import {cloudflareMockServer, mf, otelServer} from "./state";
 import {expect} from "chai";
 import {Utils} from "./utils";
@@ -8,15 +8,43 @@ Given('Worker is configured to point to mock Cloudflare API', function () {
     mf.config.cloudflareApiUrl = cloudflareMockServer.url();
 });
 
+Given('Cloudflare API returns errors for {string}', function (dataset: string) {
+    cloudflareMockServer.failingDatasets.add(dataset);
+});
+
+Given('Cloudflare API lists {int} zones', function (zoneCount: number) {
+    cloudflareMockServer.zoneCount = zoneCount;
+});
+
+Given('Worker is configured with {string} set to {string}', function (name: string, value: string) {
+    mf.config.vars[name] = value;
+});
+
+Given('Worker is configured to discover accounts', function () {
+    mf.config.vars["CLOUDFLARE_ACCOUNT_ID"] = "";
+});
+
+Given('Worker is configured without the EXPORTER_STATE KV namespace', function () {
+    mf.config.stateKvNamespace = false;
+});
+
 Given('Worker is configured to send metrics to a mock OpenTelemetry collector', function () {
     otelServer.start();
     mf.config.metricsUrl = otelServer.metricsUrl();
 });
 
+Given('Worker is configured to send logs to a mock OpenTelemetry collector', function () {
+    mf.config.vars["LOGS_URL"] = otelServer.logsUrl();
+});
+
 When('Worker is triggered', async function () {
     await mf.trigger();
 });
 
+When('Worker is triggered for {int} consecutive minutes', async function (minutes: number) {
+    await mf.triggerForConsecutiveMinutes(minutes);
+});
+
 Then('Worker metrics are published', async function () {
     await Utils.waitUntil(() => otelServer.getMetrics().length > 0);
     let metrics = otelServer.getMetrics();
@@ -28,6 +56,164 @@ Then('Metric name should include {string}', function (metricName: string) {
     expect(metricNames).to.include(metricName);
 });
 
+Then('Sum {string} should have attribute {string} set to {string}', function (metricName: string, key: string, value: string) {
+    let metric = otelServer.getMetric(metricName);
+    expect(metric?.sum).to.not.be.undefined;
+    let values = metric!.sum!.dataPoints
+        .flatMap((dataPoint) => dataPoint.attributes)
+        .filter((attribute) => attribute.key === key)
+        .map((attribute) => attribute.value.stringValue);
+    expect(values).to.include(value);
+});
+
+Then('Sum {string} should have a total of {float}', function (metricName: string, total: number) {
+    let metric = otelServer.getMetric(metricName);
+    expect(metric?.sum).to.not.be.undefined;
+    let values = metric!.sum!.dataPoints.map((dataPoint) => dataPoint.asDouble!);
+    expect(values.reduce((a, b) => a + b, 0)).to.equal(total);
+});
+
+Then('Gauge {string} for collector {string} should have value {float}', function (metricName: string, collector: string, value: number) {
+    let metric = otelServer.getMetric(metricName);
+    expect(metric?.gauge).to.not.be.undefined;
+    let values = metric!.gauge!.dataPoints
+        .filter((dataPoint) => dataPoint.attributes.some((attribute) => attribute.key === "collector" && attribute.value.stringValue === collector))
+        .map((dataPoint) => dataPoint.asDouble);
+    expect(values).to.deep.equal([value]);
+});
+
+Then('Cloudflare API should have received {int} queries for {string}', function (count: number, dataset: string) {
+    expect(cloudflareMockServer.queryCount(dataset)).to.equal(count);
+});
+
+Then('Cloudflare API should have received {int} requests for {string}', function (count: number, path: string) {
+    expect(cloudflareMockServer.restRequests.filter((url) => url.startsWith(path))).to.have.length(count);
+});
+
+Then('Cloudflare API queries should set {string} to a time', function (name: string) {
+    let values = cloudflareMockServer.queryVariables()
+        .filter((variables) => variables[name] !== undefined)
+        .map((variables) => new Date(variables[name]).getTime());
+    expect(values).to.not.be.empty;
+    expect(values.every((value) => !isNaN(value))).to.be.true;
+});
+
+Then('Cloudflare API should have been queried for a complete minute ending at least {int} minutes ago', function (lag: number) {
+    let variables = cloudflareMockServer.queryVariables();
+    expect(variables).to.have.length.gte(1);
+    for (let query of variables) {
+        let start = new Date(query.datetimeStart).getTime();
+        let end = new Date(query.datetimeEnd).getTime();
+        expect(start % 60000).to.equal(0);
+        expect(end - start).to.equal(60000);
+        expect(end).to.be.lte(Date.now() - lag * 60000);
+    }
+});
+
+Then('Sum {string} should have data points for {int} minutes', function (metricName: string, minutes: number) {
+    let metric = otelServer.getMetric(metricName);
+    expect(metric?.sum).to.not.be.undefined;
+    let times = new Set<number>();
+    for (let dataPoint of metric!.sum!.dataPoints) {
+        let start = Math.round(Number(dataPoint.startTimeUnixNano) / 1e6);
+        let time = Math.round(Number(dataPoint.timeUnixNano) / 1e6);
+        expect(start % 60000).to.equal(0);
+        expect(time - start).to.equal(60000);
+        expect(time).to.be.lte(Date.now());
+        times.add(time);
+    }
+    expect(times.size).to.equal(minutes);
+});
+
+Then('Sum metrics should have {string} temporality', function (temporality: string) {
+    let expected = temporality === "delta" ? 1 : 2;
+    let sums = otelServer.getSums();
+    expect(sums).to.have.length.gte(1);
+    for (let sum of sums) {
+        expect(sum.aggregationTemporality).to.equal(expected);
+    }
+});
+
+Then('Sum {string} for {string} set to {string} should have a running total of {float}', function (metricName: string, key: string, value: string, total: number) {
+    let exports = otelServer.getMetrics();
+    let dataPoint = Utils.latestDataPoint(otelServer.getMetric(metricName, exports.slice(-1)), key, value);
+    expect(dataPoint?.asDouble).to.equal(total);
+});
+
+Then('Sum {string} for {string} set to {string} should keep the start time of the first run', function (metricName: string, key: string, value: string) {
+    let exports = otelServer.getMetrics();
+    expect(exports).to.have.length.gte(2);
+    let first = Utils.latestDataPoint(otelServer.getMetric(metricName, exports.slice(0, 1)), key, value);
+    let last = Utils.latestDataPoint(otelServer.getMetric(metricName, exports.slice(-1)), key, value);
+    expect(first).to.not.be.undefined;
+    expect(last?.startTimeUnixNano).to.equal(first!.startTimeUnixNano);
+    expect(last?.timeUnixNano).to.not.equal(first!.timeUnixNano);
+});
+
+Then('Metric {string} should be a summary with quantile {float}', function (metricName: string, quantile: number) {
+    let metric = otelServer.getMetric(metricName);
+    expect(metric?.summary).to.not.be.undefined;
+    let dataPoints = metric!.summary!.dataPoints;
+    expect(dataPoints).to.have.length.gte(1);
+    for (let dataPoint of dataPoints) {
+        let quantiles = dataPoint.quantileValues!.map((value) => value.quantile);
+        expect(quantiles).to.include(quantile);
+    }
+});
+
+Then('Metrics should have the following names and units:', function (dataTable: DataTable) {
+    for (let row of dataTable.hashes()) {
+        let metric = otelServer.getMetric(row.name);
+        expect(metric, row.name).to.not.be.undefined;
+        expect(metric!.unit, row.name).to.equal(row.unit);
+    }
+});
+
+Then('Resource attribute {string} should be {string}', function (key: string, value: string) {
+    let attributes = otelServer.getResourceAttributes();
+    expect(attributes.get(key)).to.equal(value);
+});
+
+Then('Metrics should be published for account {string}', function (accountId: string) {
+    expect(otelServer.getAccountIds()).to.include(accountId);
+});
+
+Then('Metrics should not be published for account {string}', function (accountId: string) {
+    expect(otelServer.getAccountIds()).to.not.include(accountId);
+});
+
+Then('Gauge {string} should have value {float}', function (metricName: string, value: number) {
+    let metric = otelServer.getMetric(metricName);
+    expect(metric?.gauge).to.not.be.undefined;
+    let values = metric!.gauge!.dataPoints.map((dataPoint) => dataPoint.asDouble);
+    expect(values).to.deep.equal([value]);
+});
+
+Then('Sum {string} should not have attribute {string}', function (metricName: string, key: string) {
+    let metric = otelServer.getMetric(metricName);
+    expect(metric?.sum).to.not.be.undefined;
+    let keys = metric!.sum!.dataPoints
+        .flatMap((dataPoint) => dataPoint.attributes)
+        .map((attribute) => attribute.key);
+    expect(keys).to.not.include(key);
+});
+
+Then('Log records should include attribute {string} set to {string}', async function (key: string, value: string) {
+    await Utils.waitUntil(() => otelServer.getLogRecords().length > 0);
+    let values = otelServer.getLogRecords()
+        .flatMap((logRecord) => logRecord.attributes)
+        .filter((attribute) => attribute.key === key)
+        .map((attribute) => attribute.value.stringValue);
+    expect(values).to.include(value);
+});
+
+Then('{int} log records should have attribute {string} set to {string}', async function (count: number, key: string, value: string) {
+    await Utils.waitUntil(() => otelServer.getLogRecords().length > 0);
+    let logRecords = otelServer.getLogRecords()
+        .filter((logRecord) => logRecord.attributes.some((attribute) => attribute.key === key && attribute.value.stringValue === value));
+    expect(logRecords).to.have.length(count);
+});
+
 After(async function () {
     await mf.dispose();
     await cloudflareMockServer.dispose();
@@ -1,14 +1,16 @@
 import http from 'http';
-import {IExportMetricsServiceRequest, IResourceMetrics} from "@opentelemetry/otlp-transformer";
+import {IExportLogsServiceRequest, IExportMetricsServiceRequest, IResourceMetrics} from "@opentelemetry/otlp-transformer";
 import {AddressInfo} from "net";
 
 export class OpenTelemetryServer {
     server: http.Server | undefined;
     metrics: IExportMetricsServiceRequest[] = [];
+    logs: IExportLogsServiceRequest[] = [];
     metricNames: Map<string, number> = new Map<string, number>();
 
     private reset() {
         this.metrics = [];
+        this.logs = [];
         this.indexMetrics();
     }
 
@@ -24,9 +26,13 @@ export class OpenTelemetryServer {
                 }
             });
             req.on('end', function() {
-                const metrics = JSON.parse(body) as IExportMetricsServiceRequest;
-                self.metrics.push(metrics);
-                self.indexMetrics();
+                if (req.url === '/v1/logs') {
+                    self.logs.push(JSON.parse(body) as IExportLogsServiceRequest);
+                } else {
+                    const metrics = JSON.parse(body) as IExportMetricsServiceRequest;
+                    self.metrics.push(metrics);
+                    self.indexMetrics();
+                }
                 res.statusCode = 200;
                 res.setHeader('Content-Type', 'text/plain');
                 res.end('OK');
@@ -58,6 +64,11 @@ export class OpenTelemetryServer {
         return `http://localhost:${port}/v1/metrics`;
     }
 
+    logsUrl() {
+        const { port } = this.server?.address() as AddressInfo;
+        return `http://localhost:${port}/v1/logs`;
+    }
+
     async dispose() {
         if (this.server != undefined) {
             this.server.close();
@@ -69,6 +80,75 @@ export class OpenTelemetryServer {
         return this.metrics;
     }
 
+    getLogRecords() {
+        let logRecords = [];
+        for (let logs of this.logs) {
+            for (let resourceLogs of logs.resourceLogs) {
+                for (let scopeLogs of resourceLogs.scopeLogs) {
+                    logRecords.push(...scopeLogs.logRecords);
+                }
+            }
+        }
+        return logRecords;
+    }
+
+    getSums() {
+        let sums = [];
+        for (let metrics of this.metrics) {
+            for (let resourceMetrics of metrics.resourceMetrics) {
+                for (let scopeMetrics of resourceMetrics.scopeMetrics) {
+                    for (let metric of scopeMetrics.metrics) {
+                        if (metric.sum !== undefined) {
+                            sums.push(metric.sum);
+                        }
+                    }
+                }
+            }
+        }
+        return sums;
+    }
+
+    getMetric(name: string, exports = this.metrics) {
+        for (let metrics of exports) {
+            for (let resourceMetrics of metrics.resourceMetrics) {
+                for (let scopeMetrics of resourceMetrics.scopeMetrics) {
+                    for (let metric of scopeMetrics.metrics) {
+                        if (metric.name === name) {
+                            return metric;
+                        }
+                    }
+                }
+            }
+        }
+        return undefined;
+    }
+
+    getResourceAttributes() {
+        let attributes = new Map<string, string>();
+        for (let metrics of this.metrics) {
+            for (let resourceMetrics of metrics.resourceMetrics) {
+                for (let attribute of resourceMetrics.resource?.attributes ?? []) {
+                    attributes.set(attribute.key, attribute.value.stringValue as string);
+                }
+            }
+        }
+        return attributes;
+    }
+
+    getAccountIds() {
+        let accountIds: string[] = [];
+        for (let metrics of this.metrics) {
+            for (let resourceMetrics of metrics.resourceMetrics) {
+                for (let attribute of resourceMetrics.resource?.attributes ?? []) {
+                    if (attribute.key === "cloud.account.id") {
+                        accountIds.push(attribute.value.stringValue as string);
+                    }
+                }
+            }
+        }
+        return accountIds;
+    }
+
     getMetricNames() {
         return Array.from(this.metricNames.keys());
     }
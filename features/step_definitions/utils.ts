@@ -1,3 +1,5 @@
+import {IMetric, INumberDataPoint} from "@opentelemetry/otlp-transformer";
+
 export class Utils {
     static waitUntil = (condition:any) => {
         return new Promise((resolve:any, reject) => {
@@ -17,4 +19,11 @@ export class Utils {
         });
     };
 
+    // The sum data point with the given attribute that covers the most recent bucket
+    static latestDataPoint = (metric: IMetric | undefined, key: string, value: string): INumberDataPoint | undefined => {
+        let dataPoints = (metric?.sum?.dataPoints ?? [])
+            .filter((dataPoint) => dataPoint.attributes.some((attribute) => attribute.key === key && attribute.value.stringValue === value));
+        dataPoints.sort((a, b) => Number(a.timeUnixNano) - Number(b.timeUnixNano));
+        return dataPoints[dataPoints.length - 1];
+    };
 }
@@ -0,0 +1,97 @@
+use std::error::Error;
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{float64, string, uint64, Time};
+use crate::metrics::{SumVec, SummaryVec, TimeBucket};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/d1_query.graphql"
+)]
+pub struct GetD1AnalyticsQuery;
+
+type D1Group = get_d1_analytics_query::GetD1AnalyticsQueryViewerAccountsD1AnalyticsAdaptiveGroups;
+
+pub struct D1Collector;
+
+impl Collector for D1Collector {
+    type Query = GetD1AnalyticsQuery;
+    type Group = D1Group;
+
+    fn name(&self) -> &'static str {
+        "d1"
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_d1_analytics_query::Variables {
+        get_d1_analytics_query::Variables {
+            account_tag: window.account_tag.clone(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_database_id: page.after_dimension(0),
+        }
+    }
+
+    fn groups(&self, response_data: get_d1_analytics_query::ResponseData) -> Result<Vec<D1Group>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.accounts.into_iter()
+            .flat_map(|account| account.d1_analytics_adaptive_groups)
+            .collect())
+    }
+
+    fn cursor(&self, group: &D1Group) -> Result<Cursor, Box<dyn Error>> {
+        let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+        Ok(Cursor {
+            datetime: dimensions.datetime_minute.clone(),
+            dimensions: vec![dimensions.database_id.clone()],
+        })
+    }
+
+    fn to_metrics(&self, _zone: Option<&Zone>, groups: &[D1Group], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        let mut d1_read_queries = SumVec::new("cloudflare_d1_read_queries", "{query}", "The number of read queries.", &["database_id"]);
+
+        let mut d1_rows_read = SumVec::new("cloudflare_d1_rows_read", "{row}", "The number of rows your queries read.", &["database_id"]);
+
+        let mut d1_rows_written = SumVec::new("cloudflare_d1_rows_written", "{row}", "The number of rows your queries wrote.", &["database_id"]);
+
+        let mut d1_write_queries = SumVec::new("cloudflare_d1_write_queries", "{query}", "The number of write queries.", &["database_id"]);
+
+        let mut d1_query_batch_response_size = SummaryVec::new("cloudflare_d1_query_batch_response_size", "By", "The total number of bytes in the response, including all returned rows and metadata.", &["database_id"]);
+        let mut d1_query_batch_time = SummaryVec::new("cloudflare_d1_query_batch_time", "ms", "Query batch response time in milliseconds.", &["database_id"]);
+
+        for group in groups.iter() {
+            let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+            let database_id = dimensions.database_id.clone();
+            let sum = group.sum.as_ref().ok_or("missing sum")?;
+            let avg = group.avg.as_ref().ok_or("missing avg")?;
+            let quantiles = group.quantiles.as_ref().ok_or("missing quantiles")?;
+
+            d1_read_queries.inc_by(&[database_id.as_str()], sum.read_queries as f64);
+            d1_rows_read.inc_by(&[database_id.as_str()], sum.rows_read as f64);
+            d1_rows_written.inc_by(&[database_id.as_str()], sum.rows_written as f64);
+            d1_write_queries.inc_by(&[database_id.as_str()], sum.write_queries as f64);
+
+            d1_query_batch_response_size.observe(&[database_id.as_str()], group.count, sum.query_batch_response_bytes, &[
+                (0.5, quantiles.query_batch_response_bytes_p50),
+                (0.9, quantiles.query_batch_response_bytes_p90),
+            ]);
+            d1_query_batch_time.observe(&[database_id.as_str()], group.count, avg.query_batch_time_ms * group.count as f64, &[
+                (0.5, quantiles.query_batch_time_ms_p50),
+                (0.9, quantiles.query_batch_time_ms_p90),
+            ]);
+        }
+
+        Ok(vec![
+            d1_read_queries.to_metric(bucket),
+            d1_rows_read.to_metric(bucket),
+            d1_rows_written.to_metric(bucket),
+            d1_write_queries.to_metric(bucket),
+            d1_query_batch_response_size.to_metric(bucket),
+            d1_query_batch_time.to_metric(bucket),
+        ])
+    }
+}
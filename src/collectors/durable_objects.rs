@@ -0,0 +1,96 @@
+use std::error::Error;
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{float32, string, uint64, Time};
+use crate::metrics::{SumVec, SummaryVec, TimeBucket};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/durableobjects_query.graphql"
+)]
+pub struct GetDurableObjectsAnalyticsQuery;
+
+type DurableObjectsGroup = get_durable_objects_analytics_query::GetDurableObjectsAnalyticsQueryViewerAccountsDurableObjectsInvocationsAdaptiveGroups;
+
+pub struct DurableObjectsCollector;
+
+impl Collector for DurableObjectsCollector {
+    type Query = GetDurableObjectsAnalyticsQuery;
+    type Group = DurableObjectsGroup;
+
+    fn name(&self) -> &'static str {
+        "durable_objects"
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_durable_objects_analytics_query::Variables {
+        get_durable_objects_analytics_query::Variables {
+            account_tag: window.account_tag.clone(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_script_name: page.after_dimension(0),
+        }
+    }
+
+    fn groups(&self, response_data: get_durable_objects_analytics_query::ResponseData) -> Result<Vec<DurableObjectsGroup>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.accounts.into_iter()
+            .flat_map(|account| account.durable_objects_invocations_adaptive_groups)
+            .collect())
+    }
+
+    fn cursor(&self, group: &DurableObjectsGroup) -> Result<Cursor, Box<dyn Error>> {
+        let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+        Ok(Cursor {
+            datetime: dimensions.datetime_minute.clone(),
+            dimensions: vec![dimensions.script_name.clone()],
+        })
+    }
+
+    fn to_metrics(&self, _zone: Option<&Zone>, groups: &[DurableObjectsGroup], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        let mut do_errors = SumVec::new("cloudflare_durable_objects_errors", "{error}", "Sum of errors", &["script_name"]);
+
+        let mut do_requests = SumVec::new("cloudflare_durable_objects_requests", "{request}", "Sum of requests", &["script_name"]);
+
+        let mut do_response_body_size = SummaryVec::new("cloudflare_durable_objects_response_body_size", "By", "Response body size", &["script_name"]);
+        let mut do_wall_time = SummaryVec::new("cloudflare_durable_objects_wall_time", "us", "Wall time", &["script_name"]);
+
+        for group in groups.iter() {
+            let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+            let script_name = dimensions.script_name.clone();
+            let sum = group.sum.as_ref().ok_or("missing sum")?;
+            let quantiles = group.quantiles.as_ref().ok_or("missing quantiles")?;
+
+            do_errors.inc_by(&[script_name.as_str()], sum.errors as f64);
+            do_requests.inc_by(&[script_name.as_str()], sum.requests as f64);
+
+            do_response_body_size.observe(&[script_name.as_str()], sum.requests, sum.response_body_size as f64, &[
+                (0.25, quantiles.response_body_size_p25 as f64),
+                (0.5, quantiles.response_body_size_p50 as f64),
+                (0.75, quantiles.response_body_size_p75 as f64),
+                (0.9, quantiles.response_body_size_p90 as f64),
+                (0.99, quantiles.response_body_size_p99 as f64),
+                (0.999, quantiles.response_body_size_p999 as f64),
+            ]);
+            do_wall_time.observe(&[script_name.as_str()], sum.requests, sum.wall_time as f64, &[
+                (0.25, quantiles.wall_time_p25 as f64),
+                (0.5, quantiles.wall_time_p50 as f64),
+                (0.75, quantiles.wall_time_p75 as f64),
+                (0.9, quantiles.wall_time_p90 as f64),
+                (0.99, quantiles.wall_time_p99 as f64),
+                (0.999, quantiles.wall_time_p999 as f64),
+            ]);
+        }
+
+        Ok(vec![
+            do_errors.to_metric(bucket),
+            do_requests.to_metric(bucket),
+            do_response_body_size.to_metric(bucket),
+            do_wall_time.to_metric(bucket),
+        ])
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::logs::v1::LogRecord;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use worker::console_log;
+use crate::collectors::pagination::{fetch_all_groups, Cursor, Page, Pagination};
+use crate::discovery::Zone;
+use crate::gql::{parse_datetime, CloudflareApi, Time};
+use crate::metrics::{merge_metrics, TimeBucket};
+
+pub mod pagination;
+
+mod d1;
+mod durable_objects;
+mod queue_backlog;
+mod queue_consumer;
+mod queue_operations;
+mod worker_cron;
+mod workers;
+mod zone_dns;
+mod zone_firewall_event_logs;
+mod zone_firewall_events;
+mod zone_health_checks;
+mod zone_http_requests;
+mod zone_load_balancing;
+mod zone_origin;
+
+/// Every metrics query groups its results by `datetimeMinute`.
+const BUCKET_WIDTH: Duration = Duration::from_secs(60);
+
+/// Whether a collector queries a dataset of the account, or of each of its zones.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Scope {
+    Account,
+    Zone,
+}
+
+/// The account, its zones for zone collectors, and the time range that collectors should query.
+#[derive(Clone)]
+pub struct QueryWindow {
+    pub account_tag: String,
+    pub zones: Vec<Zone>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl QueryWindow {
+    pub fn zone_tags(&self) -> Vec<String> {
+        self.zones.iter().map(|zone| zone.id.clone()).collect()
+    }
+
+    /// The same window, restricted to a single zone.
+    fn for_zone(&self, zone_tag: &str) -> QueryWindow {
+        QueryWindow {
+            zones: self.zones.iter().filter(|zone| zone.id == zone_tag).cloned().collect(),
+            ..self.clone()
+        }
+    }
+}
+
+/// A group of a zone dataset along with the zone it belongs to, as zone datasets are queried for all the zones of an account at once.
+pub struct ZoneGroup<G> {
+    pub zone_tag: String,
+    pub group: G,
+}
+
+impl<G> ZoneGroup<G> {
+    pub fn tag_all(zone_tag: String, groups: Vec<G>) -> impl Iterator<Item = ZoneGroup<G>> {
+        groups.into_iter().map(move |group| ZoneGroup { zone_tag: zone_tag.clone(), group })
+    }
+}
+
+/// A Cloudflare Analytics dataset: the GraphQL query used to fetch it and how its response maps to metrics.
+///
+/// Adding a new dataset only requires a new module implementing this trait and an entry in [`registry`].
+pub trait Collector {
+    type Query: GraphQLQuery;
+    /// A single row of the dataset, as returned by the query.
+    type Group;
+
+    /// Short, stable name used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Zone collectors query all the zones of the account in the [`QueryWindow`] at once, with `zoneTag_in`.
+    fn scope(&self) -> Scope {
+        Scope::Account
+    }
+
+    /// The zone a group belongs to, for zone collectors. Each zone is paginated on its own, as the `limit` applies per zone.
+    fn zone_tag<'a>(&self, _group: &'a Self::Group) -> Option<&'a str> {
+        None
+    }
+
+    /// The query must order its results by datetime and then by the dimensions returned by [`Collector::cursor`].
+    fn variables(&self, window: &QueryWindow, page: &Page) -> <Self::Query as GraphQLQuery>::Variables;
+
+    fn groups(&self, data: <Self::Query as GraphQLQuery>::ResponseData) -> Result<Vec<Self::Group>, Box<dyn Error>>;
+
+    fn cursor(&self, group: &Self::Group) -> Result<Cursor, Box<dyn Error>>;
+
+    /// Start of the minute bucket the group belongs to, i.e. the datetime of its cursor for datasets grouped by `datetimeMinute`.
+    fn bucket(&self, group: &Self::Group) -> Result<Time, Box<dyn Error>> {
+        Ok(self.cursor(group)?.datetime)
+    }
+
+    /// Builds the metrics for the groups of a single minute bucket. `zone` is the zone the groups belong to, for zone collectors.
+    fn to_metrics(&self, zone: Option<&Zone>, groups: &[Self::Group], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>>;
+
+    /// Builds log records for the groups of a single minute bucket, for datasets of individual events.
+    fn to_log_records(&self, _zone: Option<&Zone>, _groups: &[Self::Group]) -> Result<Vec<LogRecord>, Box<dyn Error>> {
+        Ok(Vec::new())
+    }
+}
+
+pub struct CollectorOutput {
+    pub metrics: Vec<Metric>,
+    pub log_records: Vec<LogRecord>,
+    pub truncated: bool,
+}
+
+pub type CollectFuture<'a> = Pin<Box<dyn Future<Output = Result<CollectorOutput, Box<dyn Error>>> + 'a>>;
+
+/// Object safe counterpart of [`Collector`] so that collectors with different queries can live in the same registry.
+pub trait DynCollector {
+    fn name(&self) -> &'static str;
+
+    fn scope(&self) -> Scope;
+
+    fn collect<'a>(&'a self, api: &'a CloudflareApi, window: &'a QueryWindow, pagination: &'a Pagination) -> CollectFuture<'a>;
+}
+
+impl<C: Collector> DynCollector for C {
+    fn name(&self) -> &'static str {
+        Collector::name(self)
+    }
+
+    fn scope(&self) -> Scope {
+        Collector::scope(self)
+    }
+
+    fn collect<'a>(&'a self, api: &'a CloudflareApi, window: &'a QueryWindow, pagination: &'a Pagination) -> CollectFuture<'a> {
+        Box::pin(async move {
+            let fetched = fetch_all_groups(self, api, window, pagination).await?;
+
+            let mut buckets: BTreeMap<(Option<String>, Time), Vec<C::Group>> = BTreeMap::new();
+            for group in fetched.groups {
+                let zone_tag = self.zone_tag(&group).map(String::from);
+                buckets.entry((zone_tag, self.bucket(&group)?)).or_default().push(group);
+            }
+            let mut metrics = Vec::new();
+            let mut log_records = Vec::new();
+            for ((zone_tag, datetime), groups) in buckets {
+                let zone = match zone_tag {
+                    Some(zone_tag) => match window.zones.iter().find(|zone| zone.id == zone_tag) {
+                        Some(zone) => Some(zone),
+                        None => {
+                            console_log!("{}: skipping groups of unknown zone {}", self.name(), zone_tag);
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
+                let start = parse_datetime(&datetime)?;
+                metrics.extend(self.to_metrics(zone, &groups, TimeBucket { start, end: start + BUCKET_WIDTH })?);
+                log_records.extend(self.to_log_records(zone, &groups)?);
+            }
+
+            Ok(CollectorOutput {
+                metrics: merge_metrics(metrics),
+                log_records,
+                truncated: fetched.truncated,
+            })
+        })
+    }
+}
+
+/// All the collectors that are run on every trigger.
+pub fn registry() -> Vec<Box<dyn DynCollector>> {
+    vec![
+        Box::new(workers::WorkersCollector),
+        Box::new(worker_cron::WorkerCronCollector),
+        Box::new(d1::D1Collector),
+        Box::new(durable_objects::DurableObjectsCollector),
+        Box::new(queue_backlog::QueueBacklogCollector),
+        Box::new(queue_consumer::QueueConsumerCollector),
+        Box::new(queue_operations::QueueOperationsCollector),
+        Box::new(zone_http_requests::ZoneHttpRequestsCollector),
+        Box::new(zone_origin::ZoneOriginCollector),
+        Box::new(zone_firewall_events::ZoneFirewallEventsCollector),
+        Box::new(zone_dns::ZoneDnsCollector),
+        Box::new(zone_load_balancing::ZoneLoadBalancingCollector),
+        Box::new(zone_health_checks::ZoneHealthChecksCollector),
+    ]
+}
+
+/// Collectors exporting individual events as logs, only run when a logs endpoint is configured.
+pub fn log_registry() -> Vec<Box<dyn DynCollector>> {
+    vec![
+        Box::new(zone_firewall_event_logs::ZoneFirewallEventLogsCollector),
+    ]
+}
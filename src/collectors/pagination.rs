@@ -0,0 +1,115 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error;
+use std::str::FromStr;
+use worker::console_log;
+use crate::collectors::{Collector, QueryWindow};
+use crate::gql::{do_graphql_query, CloudflareApi, Time};
+
+pub const DEFAULT_PAGE_SIZE: i64 = 9999;
+pub const DEFAULT_MAX_PAGES: usize = 10;
+
+/// How many groups to request per query, and how many queries a collector may issue per trigger.
+pub struct Pagination {
+    pub page_size: i64,
+    pub max_pages: usize,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Pagination {
+            page_size: DEFAULT_PAGE_SIZE,
+            max_pages: DEFAULT_MAX_PAGES,
+        }
+    }
+}
+
+/// A page of results: at most `limit` groups, coming after the `after` cursor in the (datetime, dimensions...)
+/// ordering requested by every query.
+///
+/// Queries express this as a keyset filter: `OR: [{datetime_gt: d}, {datetime: d, dim1_gt: x1}, {datetime: d, dim1: x1, dim2_gt: x2}, ...]`.
+pub struct Page {
+    pub limit: i64,
+    pub after: Cursor,
+}
+
+impl Page {
+    /// The first page starts just before the window, so every group of the window comes after it whatever its dimensions.
+    fn first(window: &QueryWindow, limit: i64) -> Page {
+        Page {
+            limit,
+            after: Cursor {
+                datetime: (window.start - chrono::Duration::minutes(1)).to_rfc3339(),
+                dimensions: Vec::new(),
+            },
+        }
+    }
+
+    pub fn after_datetime(&self) -> Time {
+        self.after.datetime.clone()
+    }
+
+    /// Value of the dimension at `index` in the cursor, or its default value on the first page.
+    pub fn after_dimension<T: FromStr + Default>(&self, index: usize) -> T {
+        self.after.dimensions.get(index)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// Value of a `Time` dimension at `index` in the cursor. On the first page it defaults to the datetime of the
+    /// cursor, as an empty string is not a valid `Time`.
+    pub fn after_time_dimension(&self, index: usize) -> Time {
+        self.after.dimensions.get(index)
+            .cloned()
+            .unwrap_or_else(|| self.after_datetime())
+    }
+}
+
+/// Position of a group in the (datetime, dimensions...) ordering requested by every query.
+#[derive(Clone, Debug)]
+pub struct Cursor {
+    pub datetime: String,
+    pub dimensions: Vec<String>,
+}
+
+pub struct FetchedGroups<G> {
+    pub groups: Vec<G>,
+    /// Set when the window could not be exhausted, meaning some groups are missing.
+    pub truncated: bool,
+}
+
+/// Fetches every group in the window, one page at a time, each page starting after the last group of the previous one.
+///
+/// Zone collectors first query all the zones at once; the zones that filled their page are then paginated on their own.
+pub async fn fetch_all_groups<C: Collector>(collector: &C, api: &CloudflareApi, window: &QueryWindow, pagination: &Pagination) -> Result<FetchedGroups<C::Group>, Box<dyn Error>> {
+    let mut groups = Vec::new();
+    let mut pending = VecDeque::from([(window.clone(), Page::first(window, pagination.page_size))]);
+    let mut pages = 0;
+    while let Some((window, page)) = pending.pop_front() {
+        if pages == pagination.max_pages {
+            console_log!("{}: window not exhausted after {} pages, results are truncated", collector.name(), pagination.max_pages);
+            return Ok(FetchedGroups { groups, truncated: true });
+        }
+        pages += 1;
+        let response_data = do_graphql_query::<C::Query>(api, collector.variables(&window, &page)).await?;
+        let page_groups = collector.groups(response_data)?;
+
+        // Number of groups and last cursor, per zone
+        let mut last: BTreeMap<Option<&str>, (i64, Cursor)> = BTreeMap::new();
+        for group in page_groups.iter() {
+            let (count, cursor) = last.entry(collector.zone_tag(group)).or_insert((0, page.after.clone()));
+            *count += 1;
+            *cursor = collector.cursor(group)?;
+        }
+        for (zone_tag, (count, cursor)) in last {
+            if count >= pagination.page_size {
+                let window = match zone_tag {
+                    Some(zone_tag) => window.for_zone(zone_tag),
+                    None => window.clone(),
+                };
+                pending.push_back((window, Page { limit: pagination.page_size, after: cursor }));
+            }
+        }
+        groups.extend(page_groups);
+    }
+    Ok(FetchedGroups { groups, truncated: false })
+}
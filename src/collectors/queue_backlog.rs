@@ -0,0 +1,77 @@
+use std::error::Error;
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{float64, string, uint64, Time};
+use crate::metrics::{GaugeVec, TimeBucket};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/queue_backlog_query.graphql"
+)]
+pub struct GetQueueBacklogAnalyticsQuery;
+
+type QueueBacklogGroup = get_queue_backlog_analytics_query::GetQueueBacklogAnalyticsQueryViewerAccountsQueueBacklogAdaptiveGroups;
+
+pub struct QueueBacklogCollector;
+
+impl Collector for QueueBacklogCollector {
+    type Query = GetQueueBacklogAnalyticsQuery;
+    type Group = QueueBacklogGroup;
+
+    fn name(&self) -> &'static str {
+        "queue_backlog"
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_queue_backlog_analytics_query::Variables {
+        get_queue_backlog_analytics_query::Variables {
+            account_tag: window.account_tag.clone(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_queue_id: page.after_dimension(0),
+        }
+    }
+
+    fn groups(&self, response_data: get_queue_backlog_analytics_query::ResponseData) -> Result<Vec<QueueBacklogGroup>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.accounts.into_iter()
+            .flat_map(|account| account.queue_backlog_adaptive_groups)
+            .collect())
+    }
+
+    fn cursor(&self, group: &QueueBacklogGroup) -> Result<Cursor, Box<dyn Error>> {
+        let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+        Ok(Cursor {
+            datetime: dimensions.datetime_minute.clone(),
+            dimensions: vec![dimensions.queue_id.clone()],
+        })
+    }
+
+    fn to_metrics(&self, _zone: Option<&Zone>, groups: &[QueueBacklogGroup], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        let mut queue_backlog_bytes = GaugeVec::new("cloudflare_queue_backlog_bytes", "By", "The average size of the backlog in bytes for sample interval", &["queue_id"]);
+
+        let mut queue_backlog_messages = GaugeVec::new("cloudflare_queue_backlog_messages", "{message}", "The average number of messages in the backlog for sample interval", &["queue_id"]);
+
+        let mut queue_backlog_sample_interval = GaugeVec::new("cloudflare_queue_backlog_sample_interval", "1", "The average value used for sample interval", &["queue_id"]);
+
+        for group in groups.iter() {
+            let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+            let queue_id = dimensions.queue_id.clone();
+            let avg = group.avg.as_ref().ok_or("missing avg")?;
+
+            queue_backlog_bytes.set(&[queue_id.as_str()], avg.bytes as f64);
+            queue_backlog_messages.set(&[queue_id.as_str()], avg.messages as f64);
+            queue_backlog_sample_interval.set(&[queue_id.as_str()], avg.sample_interval);
+        }
+
+        Ok(vec![
+            queue_backlog_bytes.to_metric(bucket),
+            queue_backlog_messages.to_metric(bucket),
+            queue_backlog_sample_interval.to_metric(bucket),
+        ])
+    }
+}
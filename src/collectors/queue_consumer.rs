@@ -0,0 +1,75 @@
+use std::error::Error;
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{float64, string, Time};
+use crate::metrics::{GaugeVec, TimeBucket};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/queue_consumer_query.graphql"
+)]
+pub struct GetQueueConsumerAnalyticsQuery;
+
+type QueueConsumerGroup = get_queue_consumer_analytics_query::GetQueueConsumerAnalyticsQueryViewerAccountsQueueConsumerMetricsAdaptiveGroups;
+
+pub struct QueueConsumerCollector;
+
+impl Collector for QueueConsumerCollector {
+    type Query = GetQueueConsumerAnalyticsQuery;
+    type Group = QueueConsumerGroup;
+
+    fn name(&self) -> &'static str {
+        "queue_consumer"
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_queue_consumer_analytics_query::Variables {
+        get_queue_consumer_analytics_query::Variables {
+            account_tag: window.account_tag.clone(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_queue_id: page.after_dimension(0),
+        }
+    }
+
+    fn groups(&self, response_data: get_queue_consumer_analytics_query::ResponseData) -> Result<Vec<QueueConsumerGroup>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.accounts.into_iter()
+            .flat_map(|account| account.queue_consumer_metrics_adaptive_groups)
+            .collect())
+    }
+
+    fn cursor(&self, group: &QueueConsumerGroup) -> Result<Cursor, Box<dyn Error>> {
+        let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+        Ok(Cursor {
+            datetime: dimensions.datetime_minute.clone(),
+            dimensions: vec![dimensions.queue_id.clone()],
+        })
+    }
+
+    fn to_metrics(&self, _zone: Option<&Zone>, groups: &[QueueConsumerGroup], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        let mut queue_consumer_concurrency = GaugeVec::new("cloudflare_queue_consumer_concurrency", "{consumer}", "The average number of concurrent consumers for sample interval", &["queue_id"]);
+
+        let mut queue_consumer_sample_interval = GaugeVec::new("cloudflare_queue_consumer_sample_interval", "1", "The average value used for sample interval", &["queue_id"]);
+
+        for group in groups.iter() {
+            let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+            let queue_id = dimensions.queue_id.as_str();
+
+            // The average is not returned for every group
+            if let Some(avg) = group.avg.as_ref() {
+                queue_consumer_concurrency.set(&[queue_id], avg.concurrency);
+                queue_consumer_sample_interval.set(&[queue_id], avg.sample_interval);
+            }
+        }
+
+        Ok(vec![
+            queue_consumer_concurrency.to_metric(bucket),
+            queue_consumer_sample_interval.to_metric(bucket),
+        ])
+    }
+}
@@ -0,0 +1,94 @@
+use std::error::Error;
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{float64, string, uint32, uint64, Time};
+use crate::metrics::{GaugeVec, SumVec, TimeBucket};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/queue_operations_query.graphql"
+)]
+pub struct GetQueueOperationsAnalyticsQuery;
+
+type QueueOperationsGroup = get_queue_operations_analytics_query::GetQueueOperationsAnalyticsQueryViewerAccountsQueueMessageOperationsAdaptiveGroups;
+
+pub struct QueueOperationsCollector;
+
+impl Collector for QueueOperationsCollector {
+    type Query = GetQueueOperationsAnalyticsQuery;
+    type Group = QueueOperationsGroup;
+
+    fn name(&self) -> &'static str {
+        "queue_operations"
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_queue_operations_analytics_query::Variables {
+        get_queue_operations_analytics_query::Variables {
+            account_tag: window.account_tag.clone(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_action_type: page.after_dimension(0),
+            after_consumer_type: page.after_dimension(1),
+            after_queue_id: page.after_dimension(2),
+            after_outcome: page.after_dimension(3),
+        }
+    }
+
+    fn groups(&self, response_data: get_queue_operations_analytics_query::ResponseData) -> Result<Vec<QueueOperationsGroup>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.accounts.into_iter()
+            .flat_map(|account| account.queue_message_operations_adaptive_groups)
+            .collect())
+    }
+
+    fn cursor(&self, group: &QueueOperationsGroup) -> Result<Cursor, Box<dyn Error>> {
+        let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+        Ok(Cursor {
+            datetime: dimensions.datetime_minute.clone(),
+            dimensions: vec![dimensions.action_type.clone(), dimensions.consumer_type.clone(), dimensions.queue_id.clone(), dimensions.outcome.clone()],
+        })
+    }
+
+    fn to_metrics(&self, _zone: Option<&Zone>, groups: &[QueueOperationsGroup], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        let mut queue_billable = SumVec::new("cloudflare_queue_operations_billable", "{operation}", "Number of Billable Operations (some message operations count as multiple billable operations)", &["action_type", "consumer_type", "queue_id", "outcome"]);
+
+        let mut queue_lag_time = GaugeVec::new("cloudflare_queue_operations_lag_time", "ms", "The average time in milliseconds between when the message was written to the queue and the current operation over the sample interval. Will always be 0 for WriteMessage operations.", &["action_type", "consumer_type", "queue_id", "outcome"]);
+
+        let mut queue_retry_count = GaugeVec::new("cloudflare_queue_operations_retry_count", "{retry}", "The average number of retries per message operation. A retry occurs after an unsucessful delivery, if the queue is configured to retry failed attempts. Only applicable to ReadMessage and DeleteMessage operations. Will always be 0 for WriteMessage operations.", &["action_type", "consumer_type", "queue_id", "outcome"]);
+
+        let mut queue_sample_interval = GaugeVec::new("cloudflare_queue_operations_sample_interval", "1", "The average value used for sample interval", &["action_type", "consumer_type", "queue_id", "outcome"]);
+
+        for group in groups.iter() {
+            let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+            let action_type = dimensions.action_type.clone();
+            let consumer_type = dimensions.consumer_type.clone();
+            let queue_id = dimensions.queue_id.clone();
+            let outcome = dimensions.outcome.clone();
+
+            let sum = group.sum.as_ref().ok_or("missing sum")?;
+            let avg = group.avg.as_ref().ok_or("missing avg")?;
+
+            queue_billable.inc_by(&[action_type.as_str(), consumer_type.as_str(),
+                queue_id.as_str(), outcome.as_str()], sum.billable_operations as f64);
+
+            queue_lag_time.set(&[action_type.as_str(), consumer_type.as_str(),
+                queue_id.as_str(), outcome.as_str()], avg.lag_time as f64);
+            queue_retry_count.set(&[action_type.as_str(), consumer_type.as_str(),
+                queue_id.as_str(), outcome.as_str()], avg.retry_count as f64);
+            queue_sample_interval.set(&[action_type.as_str(), consumer_type.as_str(),
+                queue_id.as_str(), outcome.as_str()], avg.sample_interval);
+        }
+
+        Ok(vec![
+            queue_billable.to_metric(bucket),
+            queue_lag_time.to_metric(bucket),
+            queue_retry_count.to_metric(bucket),
+            queue_sample_interval.to_metric(bucket),
+        ])
+    }
+}
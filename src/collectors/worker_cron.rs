@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use chrono::{DurationRound, NaiveDateTime};
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{string, uint32, Time};
+use crate::metrics::{SumVec, SummaryVec, TimeBucket};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/worker_cron_query.graphql"
+)]
+pub struct GetWorkerCronQuery;
+
+type WorkerCronInvocation = get_worker_cron_query::GetWorkerCronQueryViewerAccountsWorkersInvocationsScheduled;
+
+const LABEL_NAMES: [&str; 4] = ["script_name", "environment_name", "cron", "status"];
+
+/// Executions of the cron triggers of workers.
+///
+/// The dataset lists individual executions, which are grouped into minute buckets.
+pub struct WorkerCronCollector;
+
+impl Collector for WorkerCronCollector {
+    type Query = GetWorkerCronQuery;
+    type Group = WorkerCronInvocation;
+
+    fn name(&self) -> &'static str {
+        "worker_cron"
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_worker_cron_query::Variables {
+        get_worker_cron_query::Variables {
+            account_tag: window.account_tag.clone(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_script_name: page.after_dimension(0),
+            after_cron: page.after_dimension(1),
+            after_scheduled_datetime: page.after_time_dimension(2),
+        }
+    }
+
+    fn groups(&self, response_data: get_worker_cron_query::ResponseData) -> Result<Vec<WorkerCronInvocation>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.accounts.into_iter()
+            .flat_map(|account| account.workers_invocations_scheduled)
+            .collect())
+    }
+
+    fn cursor(&self, invocation: &WorkerCronInvocation) -> Result<Cursor, Box<dyn Error>> {
+        Ok(Cursor {
+            datetime: invocation.datetime.clone(),
+            dimensions: vec![
+                invocation.script_name.clone(),
+                invocation.cron.clone(),
+                invocation.scheduled_datetime.clone(),
+            ],
+        })
+    }
+
+    fn bucket(&self, invocation: &WorkerCronInvocation) -> Result<Time, Box<dyn Error>> {
+        truncate_to_minute(&invocation.datetime)
+    }
+
+    fn to_metrics(&self, _zone: Option<&Zone>, invocations: &[WorkerCronInvocation], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        let mut executions = SumVec::new("cloudflare_worker_cron_executions", "{execution}", "Number of cron trigger executions", &LABEL_NAMES);
+        let mut cpu_time = SummaryVec::new("cloudflare_worker_cron_cpu_time", "us", "CPU time of cron trigger executions", &LABEL_NAMES);
+        let mut cpu_time_by_labels: BTreeMap<[&str; 4], (u64, f64)> = BTreeMap::new();
+
+        for invocation in invocations.iter() {
+            let labels = [
+                invocation.script_name.as_str(),
+                invocation.environment_name.as_str(),
+                invocation.cron.as_str(),
+                invocation.status.as_str(),
+            ];
+            executions.inc_by(&labels, 1.0);
+            let (count, sum) = cpu_time_by_labels.entry(labels).or_default();
+            *count += 1;
+            *sum += invocation.cpu_time_us as f64;
+        }
+        for (labels, (count, sum)) in cpu_time_by_labels {
+            cpu_time.observe(&labels, count, sum, &[]);
+        }
+
+        Ok(vec![
+            executions.to_metric(bucket),
+            cpu_time.to_metric(bucket),
+        ])
+    }
+}
+
+fn truncate_to_minute(datetime: &str) -> Result<String, Box<dyn Error>> {
+    let datetime = NaiveDateTime::parse_from_str(datetime, "%+")?;
+    Ok(datetime.and_utc().duration_trunc(chrono::Duration::minutes(1))?.to_rfc3339())
+}
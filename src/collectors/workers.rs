@@ -0,0 +1,135 @@
+use std::error::Error;
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{float32, float64, string, uint64, Time};
+use crate::metrics::{SumVec, SummaryVec, TimeBucket};
+
+// The paths are relative to the directory where your `Cargo.toml` is located.
+// Both json and the GraphQL schema language are supported as sources for the schema
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/workers_query.graphql"
+)]
+pub struct GetWorkersAnalyticsQuery;
+
+type WorkersGroup = get_workers_analytics_query::GetWorkersAnalyticsQueryViewerAccountsWorkersInvocationsAdaptive;
+
+/// Labels of every worker metric. Dimensions that do not apply to a script, e.g. the dispatch namespace, are empty and left out.
+const LABEL_NAMES: [&str; 6] = ["script_name", "status", "script_version", "environment_name", "usage_model", "dispatch_namespace_name"];
+
+pub struct WorkersCollector;
+
+impl Collector for WorkersCollector {
+    type Query = GetWorkersAnalyticsQuery;
+    type Group = WorkersGroup;
+
+    fn name(&self) -> &'static str {
+        "workers"
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_workers_analytics_query::Variables {
+        get_workers_analytics_query::Variables {
+            account_tag: window.account_tag.clone(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_script_name: page.after_dimension(0),
+            after_status: page.after_dimension(1),
+            after_script_version: page.after_dimension(2),
+            after_environment_name: page.after_dimension(3),
+            after_usage_model: page.after_dimension(4),
+            after_dispatch_namespace_name: page.after_dimension(5),
+        }
+    }
+
+    fn groups(&self, response_data: get_workers_analytics_query::ResponseData) -> Result<Vec<WorkersGroup>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.accounts.into_iter()
+            .flat_map(|account| account.workers_invocations_adaptive)
+            .collect())
+    }
+
+    fn cursor(&self, worker: &WorkersGroup) -> Result<Cursor, Box<dyn Error>> {
+        let dimensions = worker.dimensions.as_ref().ok_or("missing dimensions")?;
+        Ok(Cursor {
+            datetime: dimensions.datetime_minute.clone(),
+            dimensions: vec![
+                dimensions.script_name.clone(),
+                dimensions.status.clone(),
+                dimensions.script_version.clone(),
+                dimensions.environment_name.clone(),
+                dimensions.usage_model.clone(),
+                dimensions.dispatch_namespace_name.clone(),
+            ],
+        })
+    }
+
+    fn to_metrics(&self, _zone: Option<&Zone>, groups: &[WorkersGroup], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        let mut worker_requests = SumVec::new("cloudflare_worker_requests", "{request}", "Sum of Requests", &LABEL_NAMES);
+
+        let mut worker_errors = SumVec::new("cloudflare_worker_errors", "{error}", "Sum of Errors", &LABEL_NAMES);
+
+        let mut worker_subrequests = SumVec::new("cloudflare_worker_subrequests", "{request}", "Sum of Subrequests", &LABEL_NAMES);
+
+        let mut worker_cpu_time = SummaryVec::new("cloudflare_worker_cpu_time", "us", "CPU time - microseconds", &LABEL_NAMES);
+        let mut worker_duration = SummaryVec::new("cloudflare_worker_duration", "GB.s", "Duration - GB*s", &LABEL_NAMES);
+        let mut worker_wall_time = SummaryVec::new("cloudflare_worker_wall_time", "us", "Wall time - microseconds", &LABEL_NAMES);
+        let mut worker_response_body_size = SummaryVec::new("cloudflare_worker_response_body_size", "By", "Response body size - bytes", &LABEL_NAMES);
+
+        for worker in groups.iter() {
+            let dimensions = worker.dimensions.as_ref().ok_or("missing dimensions")?;
+            let labels = [
+                dimensions.script_name.as_str(),
+                dimensions.status.as_str(),
+                dimensions.script_version.as_str(),
+                dimensions.environment_name.as_str(),
+                dimensions.usage_model.as_str(),
+                dimensions.dispatch_namespace_name.as_str(),
+            ];
+            let sum = worker.sum.as_ref().ok_or("missing sum")?;
+            let quantiles = worker.quantiles.as_ref().ok_or("missing quantiles")?;
+
+            worker_requests.inc_by(&labels, sum.requests as f64);
+            worker_errors.inc_by(&labels, sum.errors as f64);
+            worker_subrequests.inc_by(&labels, sum.subrequests as f64);
+            worker_cpu_time.observe(&labels, sum.requests, sum.cpu_time_us as f64, &[
+                (0.5, quantiles.cpu_time_p50 as f64),
+                (0.75, quantiles.cpu_time_p75 as f64),
+                (0.99, quantiles.cpu_time_p99 as f64),
+                (0.999, quantiles.cpu_time_p999 as f64),
+            ]);
+            worker_duration.observe(&labels, sum.requests, sum.duration, &[
+                (0.5, quantiles.duration_p50 as f64),
+                (0.75, quantiles.duration_p75 as f64),
+                (0.99, quantiles.duration_p99 as f64),
+                (0.999, quantiles.duration_p999 as f64),
+            ]);
+            worker_wall_time.observe(&labels, sum.requests, sum.wall_time as f64, &[
+                (0.5, quantiles.wall_time_p50 as f64),
+                (0.75, quantiles.wall_time_p75 as f64),
+                (0.99, quantiles.wall_time_p99 as f64),
+                (0.999, quantiles.wall_time_p999 as f64),
+            ]);
+            worker_response_body_size.observe(&labels, sum.requests, sum.response_body_size as f64, &[
+                (0.5, quantiles.response_body_size_p50 as f64),
+                (0.75, quantiles.response_body_size_p75 as f64),
+                (0.99, quantiles.response_body_size_p99 as f64),
+                (0.999, quantiles.response_body_size_p999 as f64),
+            ]);
+        }
+
+        Ok(vec![
+            worker_requests.to_metric(bucket),
+            worker_errors.to_metric(bucket),
+            worker_subrequests.to_metric(bucket),
+            worker_cpu_time.to_metric(bucket),
+            worker_duration.to_metric(bucket),
+            worker_wall_time.to_metric(bucket),
+            worker_response_body_size.to_metric(bucket),
+        ])
+    }
+}
@@ -0,0 +1,97 @@
+use std::error::Error;
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow, Scope, ZoneGroup};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{float64, string, uint64, Time};
+use crate::metrics::{SumVec, SummaryVec, TimeBucket};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/zone_dns_query.graphql"
+)]
+pub struct GetZoneDnsQuery;
+
+type ZoneDnsGroup = get_zone_dns_query::GetZoneDnsQueryViewerZonesDnsAnalyticsAdaptiveGroups;
+
+/// Queries answered by the authoritative DNS of the zone.
+pub struct ZoneDnsCollector;
+
+impl Collector for ZoneDnsCollector {
+    type Query = GetZoneDnsQuery;
+    type Group = ZoneGroup<ZoneDnsGroup>;
+
+    fn name(&self) -> &'static str {
+        "zone_dns"
+    }
+
+    fn scope(&self) -> Scope {
+        Scope::Zone
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_zone_dns_query::Variables {
+        get_zone_dns_query::Variables {
+            zone_tags: window.zone_tags(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_query_type: page.after_dimension(0),
+            after_response_code: page.after_dimension(1),
+            after_colo_name: page.after_dimension(2),
+        }
+    }
+
+    fn groups(&self, response_data: get_zone_dns_query::ResponseData) -> Result<Vec<ZoneGroup<ZoneDnsGroup>>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.zones.into_iter()
+            .flat_map(|zone| ZoneGroup::tag_all(zone.zone_tag, zone.dns_analytics_adaptive_groups))
+            .collect())
+    }
+
+    fn zone_tag<'a>(&self, group: &'a ZoneGroup<ZoneDnsGroup>) -> Option<&'a str> {
+        Some(&group.zone_tag)
+    }
+
+    fn cursor(&self, group: &ZoneGroup<ZoneDnsGroup>) -> Result<Cursor, Box<dyn Error>> {
+        let dimensions = group.group.dimensions.as_ref().ok_or("missing dimensions")?;
+        Ok(Cursor {
+            datetime: dimensions.datetime_minute.clone(),
+            dimensions: vec![
+                dimensions.query_type.clone(),
+                dimensions.response_code.clone(),
+                dimensions.colo_name.clone(),
+            ],
+        })
+    }
+
+    fn to_metrics(&self, zone: Option<&Zone>, groups: &[ZoneGroup<ZoneDnsGroup>], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        let Some(zone) = zone else {
+            return Ok(Vec::new());
+        };
+        let zone_name = zone.name.as_str();
+        let mut queries = SumVec::new("cloudflare_zone_dns_queries", "{query}", "Number of DNS queries", &["zone_name", "query_type", "response_code", "colo"]);
+        let mut processing_time = SummaryVec::new("cloudflare_zone_dns_processing_time", "us", "Time taken to answer DNS queries", &["zone_name", "query_type", "response_code", "colo"]);
+
+        for group in groups.iter().map(|zone_group| &zone_group.group) {
+            let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+            let labels = [zone_name, dimensions.query_type.as_str(), dimensions.response_code.as_str(), dimensions.colo_name.as_str()];
+
+            queries.inc_by(&labels, group.count as f64);
+            // Quantiles are not returned for every group
+            if let (Some(avg), Some(quantiles)) = (group.avg.as_ref(), group.quantiles.as_ref()) {
+                processing_time.observe(&labels, group.count, avg.processing_time_us * group.count as f64, &[
+                    (0.5, quantiles.processing_time_us_p50),
+                    (0.9, quantiles.processing_time_us_p90),
+                    (0.99, quantiles.processing_time_us_p99),
+                ]);
+            }
+        }
+
+        Ok(vec![
+            queries.to_metric(bucket),
+            processing_time.to_metric(bucket),
+        ])
+    }
+}
@@ -0,0 +1,114 @@
+use std::error::Error;
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue};
+use opentelemetry_proto::tonic::logs::v1::{LogRecord, SeverityNumber};
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow, Scope, ZoneGroup};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{parse_datetime, string, Time};
+use crate::metrics::{to_key_value, to_unix_nano, TimeBucket};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/zone_firewall_event_logs_query.graphql"
+)]
+pub struct GetZoneFirewallEventLogsQuery;
+
+type ZoneFirewallEvent = get_zone_firewall_event_logs_query::GetZoneFirewallEventLogsQueryViewerZonesFirewallEventsAdaptive;
+
+/// Individual firewall events, exported as log records rather than metrics.
+pub struct ZoneFirewallEventLogsCollector;
+
+impl Collector for ZoneFirewallEventLogsCollector {
+    type Query = GetZoneFirewallEventLogsQuery;
+    type Group = ZoneGroup<ZoneFirewallEvent>;
+
+    fn name(&self) -> &'static str {
+        "zone_firewall_event_logs"
+    }
+
+    fn scope(&self) -> Scope {
+        Scope::Zone
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_zone_firewall_event_logs_query::Variables {
+        get_zone_firewall_event_logs_query::Variables {
+            zone_tags: window.zone_tags(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_ray_name: page.after_dimension(0),
+            after_rule_id: page.after_dimension(1),
+            after_source: page.after_dimension(2),
+            after_action: page.after_dimension(3),
+        }
+    }
+
+    fn groups(&self, response_data: get_zone_firewall_event_logs_query::ResponseData) -> Result<Vec<ZoneGroup<ZoneFirewallEvent>>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.zones.into_iter()
+            .flat_map(|zone| ZoneGroup::tag_all(zone.zone_tag, zone.firewall_events_adaptive))
+            .collect())
+    }
+
+    fn zone_tag<'a>(&self, group: &'a ZoneGroup<ZoneFirewallEvent>) -> Option<&'a str> {
+        Some(&group.zone_tag)
+    }
+
+    fn cursor(&self, zone_event: &ZoneGroup<ZoneFirewallEvent>) -> Result<Cursor, Box<dyn Error>> {
+        let event = &zone_event.group;
+        Ok(Cursor {
+            datetime: event.datetime.clone(),
+            dimensions: vec![
+                event.ray_name.clone(),
+                event.rule_id.clone(),
+                event.source.clone(),
+                event.action.clone(),
+            ],
+        })
+    }
+
+    fn to_metrics(&self, _zone: Option<&Zone>, _events: &[ZoneGroup<ZoneFirewallEvent>], _bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        Ok(Vec::new())
+    }
+
+    fn to_log_records(&self, zone: Option<&Zone>, events: &[ZoneGroup<ZoneFirewallEvent>]) -> Result<Vec<LogRecord>, Box<dyn Error>> {
+        let Some(zone) = zone else {
+            return Ok(Vec::new());
+        };
+        let zone_name = zone.name.as_str();
+        let observed_time_unix_nano = to_unix_nano(chrono::Utc::now().into());
+        events.iter().map(|zone_event| &zone_event.group).map(|event| {
+            let (severity_number, severity_text) = match event.action.as_str() {
+                "block" | "drop" => (SeverityNumber::Warn, "WARN"),
+                _ => (SeverityNumber::Info, "INFO"),
+            };
+            let body = if event.description.is_empty() { event.action.clone() } else { event.description.clone() };
+            Ok(LogRecord {
+                time_unix_nano: to_unix_nano(parse_datetime(&event.datetime)?),
+                observed_time_unix_nano,
+                severity_number: severity_number as i32,
+                severity_text: severity_text.to_string(),
+                body: Some(AnyValue { value: Some(any_value::Value::StringValue(body)) }),
+                attributes: vec![
+                    to_key_value("zone_name", zone_name),
+                    to_key_value("ray_id", &event.ray_name),
+                    to_key_value("action", &event.action),
+                    to_key_value("source", &event.source),
+                    to_key_value("rule_id", &event.rule_id),
+                    to_key_value("client_ip", &event.client_ip),
+                    to_key_value("country", &event.client_country_name),
+                    to_key_value("host", &event.client_request_http_host),
+                    to_key_value("method", &event.client_request_http_method_name),
+                    to_key_value("path", &event.client_request_path),
+                ],
+                dropped_attributes_count: 0,
+                flags: 0,
+                trace_id: vec![],
+                span_id: vec![],
+            })
+        }).collect()
+    }
+}
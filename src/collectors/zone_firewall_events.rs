@@ -0,0 +1,92 @@
+use std::error::Error;
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow, Scope, ZoneGroup};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{string, uint64, Time};
+use crate::metrics::{SumVec, TimeBucket};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/zone_firewall_events_query.graphql"
+)]
+pub struct GetZoneFirewallEventsQuery;
+
+type ZoneFirewallEventsGroup = get_zone_firewall_events_query::GetZoneFirewallEventsQueryViewerZonesFirewallEventsAdaptiveGroups;
+
+pub struct ZoneFirewallEventsCollector;
+
+impl Collector for ZoneFirewallEventsCollector {
+    type Query = GetZoneFirewallEventsQuery;
+    type Group = ZoneGroup<ZoneFirewallEventsGroup>;
+
+    fn name(&self) -> &'static str {
+        "zone_firewall_events"
+    }
+
+    fn scope(&self) -> Scope {
+        Scope::Zone
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_zone_firewall_events_query::Variables {
+        get_zone_firewall_events_query::Variables {
+            zone_tags: window.zone_tags(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_action: page.after_dimension(0),
+            after_source: page.after_dimension(1),
+            after_rule_id: page.after_dimension(2),
+            after_client_country_name: page.after_dimension(3),
+        }
+    }
+
+    fn groups(&self, response_data: get_zone_firewall_events_query::ResponseData) -> Result<Vec<ZoneGroup<ZoneFirewallEventsGroup>>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.zones.into_iter()
+            .flat_map(|zone| ZoneGroup::tag_all(zone.zone_tag, zone.firewall_events_adaptive_groups))
+            .collect())
+    }
+
+    fn zone_tag<'a>(&self, group: &'a ZoneGroup<ZoneFirewallEventsGroup>) -> Option<&'a str> {
+        Some(&group.zone_tag)
+    }
+
+    fn cursor(&self, group: &ZoneGroup<ZoneFirewallEventsGroup>) -> Result<Cursor, Box<dyn Error>> {
+        let dimensions = group.group.dimensions.as_ref().ok_or("missing dimensions")?;
+        Ok(Cursor {
+            datetime: dimensions.datetime_minute.clone(),
+            dimensions: vec![
+                dimensions.action.clone(),
+                dimensions.source.clone(),
+                dimensions.rule_id.clone(),
+                dimensions.client_country_name.clone(),
+            ],
+        })
+    }
+
+    fn to_metrics(&self, zone: Option<&Zone>, groups: &[ZoneGroup<ZoneFirewallEventsGroup>], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        let Some(zone) = zone else {
+            return Ok(Vec::new());
+        };
+        let zone_name = zone.name.as_str();
+        let mut firewall_events = SumVec::new("cloudflare_zone_firewall_events", "{event}", "Number of firewall events, e.g. requests blocked or challenged by WAF, rate limiting, bot management or custom rules", &["zone_name", "action", "source", "rule_id", "country"]);
+
+        for group in groups.iter().map(|zone_group| &zone_group.group) {
+            let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+            firewall_events.inc_by(&[
+                zone_name,
+                dimensions.action.as_str(),
+                dimensions.source.as_str(),
+                dimensions.rule_id.as_str(),
+                dimensions.client_country_name.as_str(),
+            ], group.count as f64);
+        }
+
+        Ok(vec![
+            firewall_events.to_metric(bucket),
+        ])
+    }
+}
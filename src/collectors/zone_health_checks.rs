@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow, Scope, ZoneGroup};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{string, uint64, uint8, Time};
+use crate::metrics::{SumVec, SummaryVec, TimeBucket};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/zone_health_checks_query.graphql"
+)]
+pub struct GetZoneHealthChecksQuery;
+
+type ZoneHealthChecksGroup = get_zone_health_checks_query::GetZoneHealthChecksQueryViewerZonesHealthCheckEventsAdaptiveGroups;
+
+/// Health checks run against the origins of the zone's load balancer pools.
+pub struct ZoneHealthChecksCollector;
+
+impl Collector for ZoneHealthChecksCollector {
+    type Query = GetZoneHealthChecksQuery;
+    type Group = ZoneGroup<ZoneHealthChecksGroup>;
+
+    fn name(&self) -> &'static str {
+        "zone_health_checks"
+    }
+
+    fn scope(&self) -> Scope {
+        Scope::Zone
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_zone_health_checks_query::Variables {
+        get_zone_health_checks_query::Variables {
+            zone_tags: window.zone_tags(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_health_check_name: page.after_dimension(0),
+            after_origin_ip: page.after_dimension(1),
+            after_health_status: page.after_dimension(2),
+            after_health_changed: page.after_dimension(3),
+        }
+    }
+
+    fn groups(&self, response_data: get_zone_health_checks_query::ResponseData) -> Result<Vec<ZoneGroup<ZoneHealthChecksGroup>>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.zones.into_iter()
+            .flat_map(|zone| ZoneGroup::tag_all(zone.zone_tag, zone.health_check_events_adaptive_groups))
+            .collect())
+    }
+
+    fn zone_tag<'a>(&self, group: &'a ZoneGroup<ZoneHealthChecksGroup>) -> Option<&'a str> {
+        Some(&group.zone_tag)
+    }
+
+    fn cursor(&self, group: &ZoneGroup<ZoneHealthChecksGroup>) -> Result<Cursor, Box<dyn Error>> {
+        let dimensions = group.group.dimensions.as_ref().ok_or("missing dimensions")?;
+        Ok(Cursor {
+            datetime: dimensions.datetime_minute.clone(),
+            dimensions: vec![
+                dimensions.health_check_name.clone(),
+                dimensions.origin_ip.clone(),
+                dimensions.health_status.clone(),
+                dimensions.health_changed.to_string(),
+            ],
+        })
+    }
+
+    fn to_metrics(&self, zone: Option<&Zone>, groups: &[ZoneGroup<ZoneHealthChecksGroup>], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        let Some(zone) = zone else {
+            return Ok(Vec::new());
+        };
+        let zone_name = zone.name.as_str();
+        let mut health_checks = SumVec::new("cloudflare_zone_health_checks", "{check}", "Number of health checks run against the origin", &["zone_name", "health_check_name", "origin_ip", "health_status"]);
+        let mut health_status_changes = SumVec::new("cloudflare_zone_health_check_status_changes", "{change}", "Number of health checks where the health status of the origin changed", &["zone_name", "health_check_name", "origin_ip", "health_status"]);
+        let mut rtt = SummaryVec::new("cloudflare_zone_health_check_rtt", "ms", "Round trip time of the health checks to the origin", &["zone_name", "health_check_name", "origin_ip"]);
+        // Only the average is available, so the round trip time is summed across health statuses without quantiles
+        let mut rtt_by_origin: BTreeMap<(&str, &str), (u64, f64)> = BTreeMap::new();
+
+        for group in groups.iter().map(|zone_group| &zone_group.group) {
+            let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+            let health_check_name = dimensions.health_check_name.as_str();
+            let origin_ip = dimensions.origin_ip.as_str();
+            let health_status = dimensions.health_status.as_str();
+
+            health_checks.inc_by(&[zone_name, health_check_name, origin_ip, health_status], group.count as f64);
+            if dimensions.health_changed == 1 {
+                health_status_changes.inc_by(&[zone_name, health_check_name, origin_ip, health_status], group.count as f64);
+            }
+            // The average is not returned for every group
+            if let Some(avg) = group.avg.as_ref() {
+                let (count, sum) = rtt_by_origin.entry((health_check_name, origin_ip)).or_default();
+                *count += group.count;
+                *sum += (avg.rtt_ms * group.count) as f64;
+            }
+        }
+        for ((health_check_name, origin_ip), (count, sum)) in rtt_by_origin {
+            rtt.observe(&[zone_name, health_check_name, origin_ip], count, sum, &[]);
+        }
+
+        Ok(vec![
+            health_checks.to_metric(bucket),
+            health_status_changes.to_metric(bucket),
+            rtt.to_metric(bucket),
+        ])
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::error::Error;
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow, Scope, ZoneGroup};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{string, uint16, uint64, Time};
+use crate::metrics::{GaugeVec, SumVec, TimeBucket};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/zone_http_requests_query.graphql"
+)]
+pub struct GetZoneHttpRequestsQuery;
+
+type ZoneHttpRequestsGroup = get_zone_http_requests_query::GetZoneHttpRequestsQueryViewerZonesHttpRequestsAdaptiveGroups;
+
+/// Cache statuses of the responses served from the Cloudflare cache.
+const CACHED_STATUSES: [&str; 4] = ["hit", "stale", "updating", "revalidated"];
+
+pub struct ZoneHttpRequestsCollector;
+
+impl Collector for ZoneHttpRequestsCollector {
+    type Query = GetZoneHttpRequestsQuery;
+    type Group = ZoneGroup<ZoneHttpRequestsGroup>;
+
+    fn name(&self) -> &'static str {
+        "zone_http_requests"
+    }
+
+    fn scope(&self) -> Scope {
+        Scope::Zone
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_zone_http_requests_query::Variables {
+        get_zone_http_requests_query::Variables {
+            zone_tags: window.zone_tags(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_client_request_http_host: page.after_dimension(0),
+            after_edge_response_status: page.after_dimension(1),
+            after_cache_status: page.after_dimension(2),
+        }
+    }
+
+    fn groups(&self, response_data: get_zone_http_requests_query::ResponseData) -> Result<Vec<ZoneGroup<ZoneHttpRequestsGroup>>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.zones.into_iter()
+            .flat_map(|zone| ZoneGroup::tag_all(zone.zone_tag, zone.http_requests_adaptive_groups))
+            .collect())
+    }
+
+    fn zone_tag<'a>(&self, group: &'a ZoneGroup<ZoneHttpRequestsGroup>) -> Option<&'a str> {
+        Some(&group.zone_tag)
+    }
+
+    fn cursor(&self, group: &ZoneGroup<ZoneHttpRequestsGroup>) -> Result<Cursor, Box<dyn Error>> {
+        let dimensions = group.group.dimensions.as_ref().ok_or("missing dimensions")?;
+        Ok(Cursor {
+            datetime: dimensions.datetime_minute.clone(),
+            dimensions: vec![
+                dimensions.client_request_http_host.clone(),
+                dimensions.edge_response_status.to_string(),
+                dimensions.cache_status.clone(),
+            ],
+        })
+    }
+
+    fn to_metrics(&self, zone: Option<&Zone>, groups: &[ZoneGroup<ZoneHttpRequestsGroup>], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        let Some(zone) = zone else {
+            return Ok(Vec::new());
+        };
+        let zone_name = zone.name.as_str();
+        let mut zone_requests = SumVec::new("cloudflare_zone_requests", "{request}", "Number of requests served to end users", &["zone_name", "host", "status"]);
+        let mut zone_bytes = SumVec::new("cloudflare_zone_bytes", "By", "Bytes served to end users", &["zone_name", "host"]);
+        let mut zone_cached_bytes = SumVec::new("cloudflare_zone_cached_bytes", "By", "Bytes served to end users from the Cloudflare cache", &["zone_name", "host"]);
+        let mut zone_cache_requests = SumVec::new("cloudflare_zone_cache_requests", "{request}", "Number of requests served to end users, by cache status", &["zone_name", "host", "cache_status"]);
+        let mut zone_cache_bytes = SumVec::new("cloudflare_zone_cache_bytes", "By", "Bytes served to end users, by cache status", &["zone_name", "host", "cache_status"]);
+        let mut zone_cache_hit_ratio = GaugeVec::new("cloudflare_zone_cache_hit_ratio", "1", "Ratio of the requests served from the Cloudflare cache", &["zone_name", "host"]);
+
+        // (cached requests, requests) per host
+        let mut host_requests: HashMap<&str, (u64, u64)> = HashMap::new();
+
+        for group in groups.iter().map(|zone_group| &zone_group.group) {
+            let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+            let host = dimensions.client_request_http_host.as_str();
+            let status = dimensions.edge_response_status.to_string();
+            let sum = group.sum.as_ref().ok_or("missing sum")?;
+
+            zone_requests.inc_by(&[zone_name, host, status.as_str()], group.count as f64);
+            zone_bytes.inc_by(&[zone_name, host], sum.edge_response_bytes as f64);
+            // Make sure the series exists even when nothing was served from the cache
+            zone_cached_bytes.inc_by(&[zone_name, host], 0.0);
+            let cache_status = dimensions.cache_status.as_str();
+            let is_cached = CACHED_STATUSES.contains(&cache_status);
+            if is_cached {
+                zone_cached_bytes.inc_by(&[zone_name, host], sum.edge_response_bytes as f64);
+            }
+
+            zone_cache_requests.inc_by(&[zone_name, host, cache_status], group.count as f64);
+            zone_cache_bytes.inc_by(&[zone_name, host, cache_status], sum.edge_response_bytes as f64);
+            let (cached_requests, requests) = host_requests.entry(host).or_default();
+            if is_cached {
+                *cached_requests += group.count;
+            }
+            *requests += group.count;
+        }
+
+        for (host, (cached_requests, requests)) in host_requests {
+            if requests > 0 {
+                zone_cache_hit_ratio.set(&[zone_name, host], cached_requests as f64 / requests as f64);
+            }
+        }
+
+        Ok(vec![
+            zone_requests.to_metric(bucket),
+            zone_bytes.to_metric(bucket),
+            zone_cached_bytes.to_metric(bucket),
+            zone_cache_requests.to_metric(bucket),
+            zone_cache_bytes.to_metric(bucket),
+            zone_cache_hit_ratio.to_metric(bucket),
+        ])
+    }
+}
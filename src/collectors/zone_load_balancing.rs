@@ -0,0 +1,93 @@
+use std::error::Error;
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow, Scope, ZoneGroup};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{string, uint64, Time};
+use crate::metrics::{SumVec, TimeBucket};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/zone_load_balancing_query.graphql"
+)]
+pub struct GetZoneLoadBalancingQuery;
+
+type ZoneLoadBalancingGroup = get_zone_load_balancing_query::GetZoneLoadBalancingQueryViewerZonesLoadBalancingRequestsAdaptiveGroups;
+
+/// Requests steered by the load balancers of the zone, by the pool and origin they were sent to.
+pub struct ZoneLoadBalancingCollector;
+
+impl Collector for ZoneLoadBalancingCollector {
+    type Query = GetZoneLoadBalancingQuery;
+    type Group = ZoneGroup<ZoneLoadBalancingGroup>;
+
+    fn name(&self) -> &'static str {
+        "zone_load_balancing"
+    }
+
+    fn scope(&self) -> Scope {
+        Scope::Zone
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_zone_load_balancing_query::Variables {
+        get_zone_load_balancing_query::Variables {
+            zone_tags: window.zone_tags(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_lb_name: page.after_dimension(0),
+            after_selected_pool_name: page.after_dimension(1),
+            after_selected_origin_name: page.after_dimension(2),
+            after_steering_policy: page.after_dimension(3),
+        }
+    }
+
+    fn groups(&self, response_data: get_zone_load_balancing_query::ResponseData) -> Result<Vec<ZoneGroup<ZoneLoadBalancingGroup>>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.zones.into_iter()
+            .flat_map(|zone| ZoneGroup::tag_all(zone.zone_tag, zone.load_balancing_requests_adaptive_groups))
+            .collect())
+    }
+
+    fn zone_tag<'a>(&self, group: &'a ZoneGroup<ZoneLoadBalancingGroup>) -> Option<&'a str> {
+        Some(&group.zone_tag)
+    }
+
+    fn cursor(&self, group: &ZoneGroup<ZoneLoadBalancingGroup>) -> Result<Cursor, Box<dyn Error>> {
+        let dimensions = group.group.dimensions.as_ref().ok_or("missing dimensions")?;
+        Ok(Cursor {
+            datetime: dimensions.datetime_minute.clone(),
+            dimensions: vec![
+                dimensions.lb_name.clone(),
+                dimensions.selected_pool_name.clone(),
+                dimensions.selected_origin_name.clone(),
+                dimensions.steering_policy.clone(),
+            ],
+        })
+    }
+
+    fn to_metrics(&self, zone: Option<&Zone>, groups: &[ZoneGroup<ZoneLoadBalancingGroup>], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        let Some(zone) = zone else {
+            return Ok(Vec::new());
+        };
+        let zone_name = zone.name.as_str();
+        let mut requests = SumVec::new("cloudflare_zone_load_balancer_requests", "{request}", "Number of requests steered by the load balancer", &["zone_name", "lb_name", "pool_name", "origin_name", "steering_policy"]);
+
+        for group in groups.iter().map(|zone_group| &zone_group.group) {
+            let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+            requests.inc_by(&[
+                zone_name,
+                dimensions.lb_name.as_str(),
+                dimensions.selected_pool_name.as_str(),
+                dimensions.selected_origin_name.as_str(),
+                dimensions.steering_policy.as_str(),
+            ], group.count as f64);
+        }
+
+        Ok(vec![
+            requests.to_metric(bucket),
+        ])
+    }
+}
@@ -0,0 +1,96 @@
+use std::error::Error;
+use graphql_client::GraphQLQuery;
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::collectors::{Collector, QueryWindow, Scope, ZoneGroup};
+use crate::collectors::pagination::{Cursor, Page};
+use crate::discovery::Zone;
+use crate::gql::{float64, string, uint16, uint64, Time};
+use crate::metrics::{SumVec, SummaryVec, TimeBucket};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/schema.graphql",
+    query_path = "gql/zone_origin_query.graphql"
+)]
+pub struct GetZoneOriginQuery;
+
+type ZoneOriginGroup = get_zone_origin_query::GetZoneOriginQueryViewerZonesHttpRequestsAdaptiveGroups;
+
+/// Requests that were forwarded to the origin, i.e. not served from the Cloudflare cache.
+pub struct ZoneOriginCollector;
+
+impl Collector for ZoneOriginCollector {
+    type Query = GetZoneOriginQuery;
+    type Group = ZoneGroup<ZoneOriginGroup>;
+
+    fn name(&self) -> &'static str {
+        "zone_origin"
+    }
+
+    fn scope(&self) -> Scope {
+        Scope::Zone
+    }
+
+    fn variables(&self, window: &QueryWindow, page: &Page) -> get_zone_origin_query::Variables {
+        get_zone_origin_query::Variables {
+            zone_tags: window.zone_tags(),
+            datetime_start: Some(window.start.to_rfc3339()),
+            datetime_end: Some(window.end.to_rfc3339()),
+            limit: page.limit,
+            after_datetime: page.after_datetime(),
+            after_client_request_http_host: page.after_dimension(0),
+            after_origin_response_status: page.after_dimension(1),
+        }
+    }
+
+    fn groups(&self, response_data: get_zone_origin_query::ResponseData) -> Result<Vec<ZoneGroup<ZoneOriginGroup>>, Box<dyn Error>> {
+        Ok(response_data.viewer.ok_or("missing viewer")?.zones.into_iter()
+            .flat_map(|zone| ZoneGroup::tag_all(zone.zone_tag, zone.http_requests_adaptive_groups))
+            .collect())
+    }
+
+    fn zone_tag<'a>(&self, group: &'a ZoneGroup<ZoneOriginGroup>) -> Option<&'a str> {
+        Some(&group.zone_tag)
+    }
+
+    fn cursor(&self, group: &ZoneGroup<ZoneOriginGroup>) -> Result<Cursor, Box<dyn Error>> {
+        let dimensions = group.group.dimensions.as_ref().ok_or("missing dimensions")?;
+        Ok(Cursor {
+            datetime: dimensions.datetime_minute.clone(),
+            dimensions: vec![
+                dimensions.client_request_http_host.clone(),
+                dimensions.origin_response_status.to_string(),
+            ],
+        })
+    }
+
+    fn to_metrics(&self, zone: Option<&Zone>, groups: &[ZoneGroup<ZoneOriginGroup>], bucket: TimeBucket) -> Result<Vec<Metric>, Box<dyn Error>> {
+        let Some(zone) = zone else {
+            return Ok(Vec::new());
+        };
+        let zone_name = zone.name.as_str();
+        let mut origin_requests = SumVec::new("cloudflare_zone_origin_requests", "{request}", "Number of requests forwarded to the origin", &["zone_name", "host", "origin_status"]);
+        let mut origin_response_duration = SummaryVec::new("cloudflare_zone_origin_response_duration", "ms", "Time taken by the origin to respond", &["zone_name", "host", "origin_status"]);
+
+        for group in groups.iter().map(|zone_group| &zone_group.group) {
+            let dimensions = group.dimensions.as_ref().ok_or("missing dimensions")?;
+            let host = dimensions.client_request_http_host.as_str();
+            let origin_status = dimensions.origin_response_status.to_string();
+
+            origin_requests.inc_by(&[zone_name, host, origin_status.as_str()], group.count as f64);
+            // Quantiles are not returned for every group
+            if let (Some(sum), Some(quantiles)) = (group.sum.as_ref(), group.quantiles.as_ref()) {
+                origin_response_duration.observe(&[zone_name, host, origin_status.as_str()], group.count, sum.origin_response_duration_ms as f64, &[
+                    (0.5, quantiles.origin_response_duration_ms_p50),
+                    (0.95, quantiles.origin_response_duration_ms_p95),
+                    (0.99, quantiles.origin_response_duration_ms_p99),
+                ]);
+            }
+        }
+
+        Ok(vec![
+            origin_requests.to_metric(bucket),
+            origin_response_duration.to_metric(bucket),
+        ])
+    }
+}
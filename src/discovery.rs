@@ -0,0 +1,212 @@
+use std::error::Error;
+use std::time::Duration;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use worker::console_log;
+use worker::kv::KvStore;
+use crate::gql::CloudflareApi;
+
+/// Name of the KV namespace binding used to cache the discovered accounts and zones.
+pub const STATE_BINDING: &str = "EXPORTER_STATE";
+
+const PAGE_SIZE: u32 = 50;
+
+const CACHE_KEY: &str = "discovery";
+
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Envelope returned by every Cloudflare REST API endpoint.
+#[derive(Deserialize)]
+struct RestResponse<T> {
+    success: bool,
+    #[serde(default)]
+    errors: Vec<RestError>,
+    result: Option<Vec<T>>,
+    result_info: Option<ResultInfo>,
+}
+
+#[derive(Deserialize)]
+struct RestError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct ResultInfo {
+    page: u32,
+    total_pages: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Zone {
+    pub id: String,
+    pub name: String,
+    pub account: ZoneAccount,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ZoneAccount {
+    pub id: String,
+}
+
+/// The accounts and zones the API token has access to, as of `refreshed_at` (unix seconds).
+///
+/// When account IDs are configured, only the zones of those accounts are listed and `accounts` is left empty.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Inventory {
+    refreshed_at: i64,
+    #[serde(default)]
+    account_ids: Vec<String>,
+    pub accounts: Vec<Account>,
+    pub zones: Vec<Zone>,
+}
+
+/// Returns the accounts and zones the API token has access to, or the zones of `account_ids` when it is not empty.
+///
+/// The result is cached in KV and only listed again once `refresh_interval` has elapsed. If listing fails,
+/// the previous result (or an empty one) is kept until then, rather than listing again on every trigger.
+pub async fn discover(api: &CloudflareApi, kv: &KvStore, account_ids: &[String], refresh_interval: Duration) -> Inventory {
+    let now = chrono::Utc::now().timestamp();
+    let cached: Option<Inventory> = match kv.get(CACHE_KEY).json().await {
+        Ok(cached) => cached.filter(|cached: &Inventory| cached.account_ids == account_ids),
+        Err(e) => {
+            console_log!("Loading discovered accounts and zones failed: {:?}", e);
+            None
+        }
+    };
+    if let Some(cached) = cached.as_ref() {
+        if now - cached.refreshed_at < refresh_interval.as_secs() as i64 {
+            return cached.clone();
+        }
+    }
+
+    console_log!("Discovering accounts and zones");
+    let inventory = match list_inventory(api, account_ids, now).await {
+        Ok(inventory) => inventory,
+        Err(e) => {
+            console_log!("Discovering accounts and zones failed, trying again in {} minutes: {:?}", refresh_interval.as_secs() / 60, e);
+            Inventory {
+                refreshed_at: now,
+                account_ids: account_ids.to_vec(),
+                ..cached.unwrap_or_default()
+            }
+        },
+    };
+    if let Err(e) = save(kv, &inventory).await {
+        console_log!("Saving discovered accounts and zones failed: {:?}", e);
+    }
+    inventory
+}
+
+async fn list_inventory(api: &CloudflareApi, account_ids: &[String], now: i64) -> Result<Inventory, Box<dyn Error>> {
+    if account_ids.is_empty() {
+        return Ok(Inventory {
+            refreshed_at: now,
+            account_ids: Vec::new(),
+            accounts: list(api, "accounts", &[]).await?,
+            zones: list(api, "zones", &[]).await?,
+        });
+    }
+    let mut zones = Vec::new();
+    for account_id in account_ids.iter() {
+        zones.extend(list(api, "zones", &[("account.id", account_id)]).await?);
+    }
+    Ok(Inventory {
+        refreshed_at: now,
+        account_ids: account_ids.to_vec(),
+        accounts: Vec::new(),
+        zones,
+    })
+}
+
+async fn save(kv: &KvStore, inventory: &Inventory) -> Result<(), Box<dyn Error>> {
+    kv.put(CACHE_KEY, serde_json::to_string(inventory)?)?.execute().await?;
+    Ok(())
+}
+
+/// Comma separated name patterns, where `*` matches any sequence of characters. Matching ignores case.
+///
+/// A name is accepted when it matches one of the allow patterns (or there are none), and none of the deny patterns.
+pub struct NameFilter {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl NameFilter {
+    pub fn new(allow: &str, deny: &str) -> NameFilter {
+        NameFilter {
+            allow: parse_patterns(allow),
+            deny: parse_patterns(deny),
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        (self.allow.is_empty() || self.allow.iter().any(|pattern| glob_match(pattern, &name)))
+            && !self.deny.iter().any(|pattern| glob_match(pattern, &name))
+    }
+}
+
+fn parse_patterns(patterns: &str) -> Vec<String> {
+    patterns.split(',')
+        .map(|pattern| pattern.trim().to_lowercase())
+        .filter(|pattern| !pattern.is_empty())
+        .collect()
+}
+
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // split always yields at least one part
+    let first = parts.next().unwrap();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // no wildcard
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+async fn list<T: DeserializeOwned>(api: &CloudflareApi, path: &str, filter: &[(&str, &str)]) -> Result<Vec<T>, Box<dyn Error>> {
+    let client = reqwest::Client::new();
+    let url = format!("{}/{}", api.rest_url.trim_end_matches('/'), path);
+    let mut items = Vec::new();
+    let mut page = 1;
+    loop {
+        let res = client.get(&url)
+            .bearer_auth(&api.api_key)
+            .query(&[("page", page), ("per_page", PAGE_SIZE)])
+            .query(filter)
+            .send().await?;
+        if !res.status().is_success() {
+            console_log!("Listing {} failed: {:?}", path, res.status());
+            return Err(Box::new(res.error_for_status().unwrap_err()));
+        }
+
+        let response_body: RestResponse<T> = res.json().await?;
+        if !response_body.success {
+            let errors: Vec<String> = response_body.errors.iter().map(|error| format!("{} ({})", error.message, error.code)).collect();
+            console_log!("Listing {} failed: {:?}", path, errors);
+            return Err(Box::new(worker::Error::JsError(format!("listing {} failed: {}", path, errors.join(", ")))));
+        }
+        items.extend(response_body.result.unwrap_or_default());
+        match response_body.result_info {
+            Some(info) if info.page < info.total_pages => page += 1,
+            _ => return Ok(items),
+        }
+    }
+}
@@ -1,17 +1,40 @@
 use std::env;
-use chrono::SubsecRound;
+use std::time::Duration;
+use chrono::DurationRound;
+use futures::{stream, StreamExt};
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
 use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
-use opentelemetry_sdk::metrics::data::{Metric, ResourceMetrics, ScopeMetrics};
-use opentelemetry_sdk::Resource;
+use opentelemetry_proto::tonic::common::v1::InstrumentationScope;
+use opentelemetry_proto::tonic::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
+use opentelemetry_proto::tonic::metrics::v1::{metric, Metric, ResourceMetrics, ScopeMetrics};
+use opentelemetry_proto::tonic::resource::v1::Resource;
 use prost::Message;
+use serde::Serialize;
 
 use worker::*;
 use worker::js_sys::Uint8Array;
 use worker::wasm_bindgen::JsValue;
-use crate::gql::{get_workers_analytics_query, do_get_workers_analytics_query, do_get_d1_analytics_query, get_d1_analytics_query, do_get_durableobjects_analytics_query, get_durable_objects_analytics_query, do_get_queue_backlog_analytics_query, get_queue_backlog_analytics_query, do_get_queue_operations_analytics_query, get_queue_operations_analytics_query};
+use crate::collectors::{QueryWindow, Scope};
+use crate::discovery::{discover, Inventory, NameFilter, Zone, DEFAULT_REFRESH_INTERVAL, STATE_BINDING};
+use crate::collectors::pagination::{Pagination, DEFAULT_MAX_PAGES, DEFAULT_PAGE_SIZE};
+use crate::gql::CloudflareApi;
+use crate::metrics::{use_delta_temporality, TimeBucket};
+use crate::resource::build_resource;
+use crate::state::{accumulate_sums, CUMULATIVE_SUMS_BINDING};
+use crate::status::{RunReport, RunStatus};
 
+mod collectors;
+mod discovery;
 mod gql;
 mod metrics;
+mod resource;
+mod state;
+mod status;
+
+const DEFAULT_MAX_CONCURRENT_QUERIES: usize = 4;
+const DEFAULT_INGESTION_LAG_MINUTES: i64 = 3;
+const SCHEMA_URL: &str = "https://github.com/j-white/cloudflare-otlp-exporter/v1.0.0";
+const DEFAULT_CLOUDFLARE_REST_API_URL: &str = "https://api.cloudflare.com/client/v4";
 
 #[worker::send]
 pub async fn do_fetch(
@@ -30,7 +53,7 @@ pub async fn do_fetch(
             http_headers.set(key, value).expect("failed to construct header");
         }
     }
-    http_headers.set("Content-Type", &*content_type).expect("failed to construct content-type header");
+    http_headers.set("Content-Type", &content_type).expect("failed to construct content-type header");
     let mut init = RequestInit::new();
     init.method = Method::Post;
     init.with_body(data).with_headers(http_headers);
@@ -43,8 +66,9 @@ pub async fn do_fetch(
 async fn fetch(_req: Request, env: Env, _ctx: Context) -> Result<Response> {
     let res = do_trigger(env).await;
     match res {
-        Ok(_) => Response::ok("OK"),
-        Err(_) => Response::error("Error", 500)
+        Ok(RunStatus::Success) => Response::ok("OK"),
+        Ok(RunStatus::PartialSuccess) => Ok(Response::ok("Partial success")?.with_status(207)),
+        Ok(RunStatus::Failure) | Err(_) => Response::error("Error", 500)
     }
 }
 
@@ -52,168 +76,302 @@ async fn fetch(_req: Request, env: Env, _ctx: Context) -> Result<Response> {
 async fn main(_req: ScheduledEvent, env: Env, _ctx: ScheduleContext) -> () {
     let res = do_trigger(env).await;
     match res {
-        Ok(_) => console_log!("OK"),
+        Ok(status) => console_log!("{:?}", status),
         Err(e) => console_log!("Error: {:?}", e),
     }
 }
 
-async fn do_trigger(env: Env) -> Result<()> {
-    let cloudflare_api_url = env.var("CLOUDFLARE_API_URL")?.to_string();
-    let cloudflare_api_key = env.var("CLOUDFLARE_API_KEY")?.to_string();
-    let cloudflare_account_id = env.var("CLOUDFLARE_ACCOUNT_ID")?.to_string();
+async fn do_trigger(env: Env) -> Result<RunStatus> {
+    let api = CloudflareApi {
+        url: env.var("CLOUDFLARE_API_URL")?.to_string(),
+        rest_url: match env.var("CLOUDFLARE_REST_API_URL") {
+            Ok(val) => val.to_string(),
+            Err(_) => DEFAULT_CLOUDFLARE_REST_API_URL.to_string(),
+        },
+        api_key: env.var("CLOUDFLARE_API_KEY")?.to_string(),
+    };
+    let kv = env.kv(STATE_BINDING).ok();
+    let account_zones = get_accounts(&env, &api, kv.as_ref()).await?;
+    if account_zones.is_empty() {
+        return Err(Error::RustError("no Cloudflare accounts to export".to_string()));
+    }
 
-    let end = chrono::Utc::now().round_subsecs(0);
-    let start = (end - chrono::Duration::minutes(1)).round_subsecs(0);
+    // Only query complete minutes, once the Analytics API has caught up with them
+    let ingestion_lag_minutes: i64 = match env.var("INGESTION_LAG_MINUTES") {
+        Ok(val) => val.to_string().parse().unwrap_or(DEFAULT_INGESTION_LAG_MINUTES).max(0),
+        Err(_) => DEFAULT_INGESTION_LAG_MINUTES,
+    };
+    let end = chrono::Utc::now().duration_trunc(chrono::Duration::minutes(1)).unwrap() - chrono::Duration::minutes(ingestion_lag_minutes);
+    let start = end - chrono::Duration::minutes(1);
+    let bucket = TimeBucket { start: start.into(), end: end.into() };
+    let mut accounts: Vec<AccountMetrics> = Vec::new();
+    let mut windows: Vec<QueryWindow> = Vec::new();
+    for (account_tag, zones) in account_zones {
+        console_log!("Exporting account {} with {} zones", account_tag, zones.len());
+        accounts.push(AccountMetrics {
+            account_id: account_tag.clone(),
+            metrics: Vec::new(),
+            log_records: Vec::new(),
+            report: RunReport::default(),
+        });
+        windows.push(QueryWindow { account_tag: account_tag.clone(), zones, start, end });
+    }
 
-    console_log!("Fetching!");
-    let mut all_metrics = Vec::new();
-
-    let result = do_get_workers_analytics_query(&cloudflare_api_url, &cloudflare_api_key, get_workers_analytics_query::Variables {
-        account_tag: cloudflare_account_id.clone(),
-        datetime_start: Some(start.to_rfc3339()),
-        datetime_end: Some(end.to_rfc3339()),
-        limit: 9999,
-    }).await;
-    match result {
-        Ok(metrics) => {
-            for metric in metrics {
-                all_metrics.push(metric);
-            }
-        },
-        Err(e) => {
-            console_log!("Querying Cloudflare API failed: {:?}", e);
-            return Err(Error::JsError(e.to_string()));
-        }
+    // Limit the number of GraphQL queries in flight to stay within the Cloudflare API rate limits
+    let max_concurrent_queries: usize = match env.var("MAX_CONCURRENT_QUERIES") {
+        Ok(val) => val.to_string().parse().unwrap_or(DEFAULT_MAX_CONCURRENT_QUERIES).max(1),
+        Err(_) => DEFAULT_MAX_CONCURRENT_QUERIES,
     };
 
-    let result = do_get_d1_analytics_query(&cloudflare_api_url, &cloudflare_api_key, get_d1_analytics_query::Variables {
-        account_tag: cloudflare_account_id.clone(),
-        datetime_start: Some(start.to_rfc3339()),
-        datetime_end: Some(end.to_rfc3339()),
-        limit: 9999,
-    }).await;
-    match result {
-        Ok(metrics) => {
-            for metric in metrics {
-                all_metrics.push(metric);
-            }
+    let pagination = Pagination {
+        page_size: match env.var("GRAPHQL_PAGE_SIZE") {
+            Ok(val) => val.to_string().parse().unwrap_or(DEFAULT_PAGE_SIZE).max(1),
+            Err(_) => DEFAULT_PAGE_SIZE,
         },
-        Err(e) => {
-            console_log!("Querying Cloudflare API failed: {:?}", e);
-            return Err(Error::JsError(e.to_string()));
-        }
+        max_pages: match env.var("GRAPHQL_MAX_PAGES") {
+            Ok(val) => val.to_string().parse().unwrap_or(DEFAULT_MAX_PAGES).max(1),
+            Err(_) => DEFAULT_MAX_PAGES,
+        },
+    };
+
+    // Events are only exported as logs when an OTLP logs endpoint is configured
+    let logs_url = match env.var("LOGS_URL") {
+        Ok(val) => Some(val.to_string()),
+        Err(_) => None,
     };
 
-    let result = do_get_durableobjects_analytics_query(&cloudflare_api_url, &cloudflare_api_key, get_durable_objects_analytics_query::Variables {
-        account_tag: cloudflare_account_id.clone(),
-        datetime_start: Some(start.to_rfc3339()),
-        datetime_end: Some(end.to_rfc3339()),
-        limit: 9999,
-    }).await;
-    match result {
-        Ok(metrics) => {
-            for metric in metrics {
-                all_metrics.push(metric);
+    console_log!("Fetching!");
+    let mut registry = collectors::registry();
+    if logs_url.is_some() {
+        registry.extend(collectors::log_registry());
+    }
+    let queries: Vec<_> = windows.iter()
+        .flat_map(|window| {
+            registry.iter()
+                .filter(move |collector| collector.scope() == Scope::Account || !window.zones.is_empty())
+                .map(move |collector| (window, collector))
+        })
+        .collect();
+    let results: Vec<_> = stream::iter(queries)
+        .map(|(window, collector)| {
+            let (api, pagination) = (&api, &pagination);
+            async move {
+                (window, collector.name(), collector.collect(api, window, pagination).await)
+            }
+        })
+        .buffered(max_concurrent_queries)
+        .collect()
+        .await;
+
+    for (window, name, result) in results {
+        let account = accounts.iter_mut().find(|account| account.account_id == window.account_tag).unwrap();
+        match result {
+            Ok(output) => {
+                account.report.record_success(name);
+                if output.truncated {
+                    account.report.record_truncated(name);
+                }
+                account.metrics.extend(output.metrics);
+                account.log_records.extend(output.log_records);
+            },
+            Err(e) => {
+                console_log!("Querying Cloudflare API for {} in account {} failed: {:?}", name, window.account_tag, e);
+                account.report.record_failure(name);
             }
-        },
-        Err(e) => {
-            console_log!("Querying Cloudflare API failed: {:?}", e);
-            return Err(Error::JsError(e.to_string()));
         }
+    }
+    console_log!("Done fetching!");
+
+    let delta_temporality: bool = match env.var("OTLP_TEMPORALITY") {
+        Ok(val) => val.to_string().eq_ignore_ascii_case("delta"),
+        Err(_) => false,
     };
+    if delta_temporality {
+        for account in accounts.iter_mut() {
+            use_delta_temporality(&mut account.metrics);
+        }
+    } else {
+        match env.durable_object(CUMULATIVE_SUMS_BINDING) {
+            Ok(namespace) => {
+                for account in accounts.iter_mut() {
+                    if let Err(e) = accumulate_sums(&namespace, &account.account_id, &mut account.metrics).await {
+                        // Exporting the values of this run as deltas would switch the temporality of the series back and forth
+                        console_log!("Keeping running totals for account {} failed, its sums are not exported: {:?}", account.account_id, e);
+                        account.metrics.retain(|metric| !matches!(metric.data, Some(metric::Data::Sum(_))));
+                        account.report.record_failure("cumulative_sums");
+                    }
+                }
+            },
+            Err(_) => {
+                console_log!("No {} Durable Object bound, sums are exported as deltas", CUMULATIVE_SUMS_BINDING);
+                for account in accounts.iter_mut() {
+                    use_delta_temporality(&mut account.metrics);
+                }
+            },
+        }
+    }
 
-    let result = do_get_queue_backlog_analytics_query(&cloudflare_api_url, &cloudflare_api_key, get_queue_backlog_analytics_query::Variables {
-        account_tag: cloudflare_account_id.clone(),
-        datetime_start: Some(start.to_rfc3339()),
-        datetime_end: Some(end.to_rfc3339()),
-        limit: 9999,
-    }).await;
-    match result {
-        Ok(metrics) => {
-            for metric in metrics {
-                all_metrics.push(metric);
-            }
-        },
-        Err(e) => {
-            console_log!("Querying Cloudflare API failed: {:?}", e);
-            return Err(Error::JsError(e.to_string()));
+    let statuses: Vec<RunStatus> = accounts.iter().map(|account| account.report.status()).collect();
+    let mut resource_metrics = Vec::new();
+    let mut resource_logs = Vec::new();
+    for mut account in accounts {
+        let resource = build_resource(&env, &account.account_id);
+        account.metrics.extend(account.report.to_metrics(bucket));
+        if !account.log_records.is_empty() {
+            resource_logs.push((resource.clone(), account.log_records));
         }
+        resource_metrics.push((resource, account.metrics));
+    }
+    do_push_metrics(&env, resource_metrics).await?;
+    if let Some(logs_url) = logs_url {
+        if !resource_logs.is_empty() {
+            do_push_logs(&env, logs_url, resource_logs).await?;
+        }
+    }
+    Ok(RunStatus::combine(&statuses))
+}
+
+/// Metrics and logs collected for a single account, exported under their own resource.
+struct AccountMetrics {
+    account_id: String,
+    metrics: Vec<Metric>,
+    log_records: Vec<LogRecord>,
+    report: RunReport,
+}
+
+/// Accounts to export along with their zones: the accounts listed in `CLOUDFLARE_ACCOUNT_ID` (comma separated),
+/// or every discovered account whose name passes the filters when it is not set.
+async fn get_accounts(env: &Env, api: &CloudflareApi, kv: Option<&kv::KvStore>) -> Result<Vec<(String, Vec<Zone>)>> {
+    let configured_account_ids: Vec<String> = match env.var("CLOUDFLARE_ACCOUNT_ID") {
+        Ok(val) => val.to_string().split(',')
+            .map(|account_id| account_id.trim().to_string())
+            .filter(|account_id| !account_id.is_empty())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    let refresh_interval = match env.var("DISCOVERY_REFRESH_MINUTES") {
+        Ok(val) => match val.to_string().parse::<u64>() {
+            Ok(minutes) => Duration::from_secs(minutes * 60),
+            Err(_) => DEFAULT_REFRESH_INTERVAL,
+        },
+        Err(_) => DEFAULT_REFRESH_INTERVAL,
     };
 
-    let result = do_get_queue_operations_analytics_query(&cloudflare_api_url, &cloudflare_api_key, get_queue_operations_analytics_query::Variables {
-        account_tag: cloudflare_account_id.clone(),
-        datetime_start: Some(start.to_rfc3339()),
-        datetime_end: Some(end.to_rfc3339()),
-        limit: 9999,
-    }).await;
-    match result {
-        Ok(metrics) => {
-            for metric in metrics {
-                all_metrics.push(metric);
-            }
+    // Without a cache, discovery would list every account and zone on every trigger
+    let inventory = match kv {
+        Some(kv) => discover(api, kv, &configured_account_ids, refresh_interval).await,
+        None if configured_account_ids.is_empty() => {
+            return Err(Error::RustError(format!("Discovering accounts requires the {} KV namespace, or set CLOUDFLARE_ACCOUNT_ID", STATE_BINDING)));
+        },
+        None => {
+            console_log!("No {} KV namespace bound, zones are not discovered", STATE_BINDING);
+            Inventory::default()
         },
-        Err(e) => {
-            console_log!("Querying Cloudflare API failed: {:?}", e);
-            return Err(Error::JsError(e.to_string()));
-        }
     };
-    console_log!("Done fetching!");
 
-    do_push_metrics(env, all_metrics).await
+    let account_filter = NameFilter::new(&get_var_or_default(env, "ACCOUNT_NAME_ALLOW"), &get_var_or_default(env, "ACCOUNT_NAME_DENY"));
+    let zone_filter = NameFilter::new(&get_var_or_default(env, "ZONE_NAME_ALLOW"), &get_var_or_default(env, "ZONE_NAME_DENY"));
+    let account_ids = if configured_account_ids.is_empty() {
+        inventory.accounts.iter()
+            .filter(|account| account_filter.matches(&account.name))
+            .map(|account| account.id.clone())
+            .collect()
+    } else {
+        configured_account_ids
+    };
+    Ok(account_ids.into_iter().map(|account_id| {
+        let zones = inventory.zones.iter()
+            .filter(|zone| zone.account.id == account_id && zone_filter.matches(&zone.name))
+            .cloned()
+            .collect();
+        (account_id, zones)
+    }).collect())
 }
 
-async fn do_push_metrics(env: Env, metrics: Vec<Metric>) -> Result<()> {
+fn get_var_or_default(env: &Env, name: &str) -> String {
+    match env.var(name) {
+        Ok(val) => val.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+async fn do_push_metrics(env: &Env, resource_metrics: Vec<(Resource, Vec<Metric>)>) -> Result<()> {
     let metrics_url = env.var("METRICS_URL")?.to_string();
+
+    console_log!("Converting metrics to OTLP.");
+    let resource_metrics = resource_metrics.into_iter().map(|(resource, metrics)| ResourceMetrics {
+        resource: Some(resource),
+        scope_metrics: vec![ScopeMetrics {
+            scope: Some(instrumentation_scope()),
+            metrics,
+            schema_url: SCHEMA_URL.to_string(),
+        }],
+        schema_url: String::new(),
+    }).collect();
+
+    let metrics = ExportMetricsServiceRequest {
+        resource_metrics,
+    };
+
+    console_log!("Posting metrics to OTLP endpoint.");
+    do_export(env, metrics_url, &metrics).await
+}
+
+async fn do_push_logs(env: &Env, logs_url: String, resource_logs: Vec<(Resource, Vec<LogRecord>)>) -> Result<()> {
+    console_log!("Converting logs to OTLP.");
+    let resource_logs = resource_logs.into_iter().map(|(resource, log_records)| ResourceLogs {
+        resource: Some(resource),
+        scope_logs: vec![ScopeLogs {
+            scope: Some(instrumentation_scope()),
+            log_records,
+            schema_url: SCHEMA_URL.to_string(),
+        }],
+        schema_url: String::new(),
+    }).collect();
+
+    let logs = ExportLogsServiceRequest {
+        resource_logs,
+    };
+
+    console_log!("Posting logs to OTLP endpoint.");
+    do_export(env, logs_url, &logs).await
+}
+
+fn instrumentation_scope() -> InstrumentationScope {
+    InstrumentationScope {
+        name: "cloudflare-otlp-exporter".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        attributes: vec![],
+        dropped_attributes_count: 0,
+    }
+}
+
+/// Sends an OTLP export request, encoded as configured by `OTLP_ENCODING`.
+async fn do_export<T: Message + Serialize>(env: &Env, url: String, request: &T) -> Result<()> {
     let otlp_headers = match env.var("OTLP_HEADERS") {
         Ok(val) => val.to_string(),
         Err(_) => String::from(""),
     };
     let otlp_encoding_json: bool = match env.var("OTLP_ENCODING") {
-        Ok(val) => match val.to_string().to_lowercase().as_str() {
-            "json" => true,
-            _ => false,
-        }
+        Ok(val) => val.to_string().eq_ignore_ascii_case("json"),
         Err(_) => false,
     };
 
-    console_log!("Converting metrics to OTLP.");
-    let library = opentelemetry::InstrumentationLibrary::new(
-        "cloudflare-otlp-exporter",
-        Some(env!("CARGO_PKG_VERSION")),
-        Some("https://github.com/j-white/cloudflare-otlp-exporter/v1.0.0"),
-        None,
-    );
-    let scope_metrics = ScopeMetrics {
-        scope: library,
-        metrics
-    };
-    let resource_metrics = ResourceMetrics {
-        resource: Resource::empty(),
-        scope_metrics: vec![scope_metrics],
-    };
-
-    let metrics = ExportMetricsServiceRequest::from(&resource_metrics);
-    let js_value: JsValue;
-    let content_type: String;
-    if otlp_encoding_json {
-        let metrics_json = serde_json::to_string(&metrics).unwrap();
-        js_value = JsValue::from_str(&metrics_json);
-        content_type = "application/json".to_string();
+    let (js_value, content_type) = if otlp_encoding_json {
+        let request_json = serde_json::to_string(request).unwrap();
+        (JsValue::from_str(&request_json), "application/json".to_string())
     } else {
-        let bytes = metrics.encode_to_vec();
+        let bytes = request.encode_to_vec();
         let array = Uint8Array::from(bytes.as_slice());
-        js_value = JsValue::from(array);
-        content_type = "application/x-protobuf".to_string();
-    }
-    console_log!("Done converting metrics to OTLP.");
+        (JsValue::from(array), "application/x-protobuf".to_string())
+    };
 
-    console_log!("Posting metrics to OTLP endpoint.");
-    let mut res = do_fetch(metrics_url, otlp_headers, Some(js_value).into(), content_type).await?;
+    let mut res = do_fetch(url, otlp_headers, Some(js_value), content_type).await?;
     let body = res.text().await?;
-    console_log!("Done posting metrics status={} body={:?}", res.status_code(), body);
+    console_log!("Done posting status={} body={:?}", res.status_code(), body);
 
     if res.status_code() != 200 {
         return Err(Error::JsError(body));
     }
-    return Ok(());
-}
\ No newline at end of file
+    Ok(())
+}
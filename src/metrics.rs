@@ -1,88 +1,209 @@
-use std::borrow::Cow;
 use std::time::SystemTime;
-use opentelemetry::KeyValue;
-use opentelemetry::metrics::Unit;
-use opentelemetry_sdk::AttributeSet;
-use opentelemetry_sdk::metrics::data::{DataPoint, Metric, Temporality};
-use prometheus::proto::{LabelPair, MetricFamily};
-use prometheus::Registry;
-
-pub fn prometheus_registry_to_opentelemetry_metrics(registry: Registry, timestamp: SystemTime) -> Vec<Metric> {
-    let mut vec = Vec::new();
-    for metric_family in registry.gather() {
-        vec.push(create_metric_prom(&metric_family, timestamp));
-    }
-    return vec;
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+use opentelemetry_proto::tonic::metrics::v1::{metric, number_data_point, summary_data_point, AggregationTemporality, Gauge, Metric, NumberDataPoint, Sum, Summary, SummaryDataPoint};
+
+/// The time range covered by a set of values.
+#[derive(Clone, Copy)]
+pub struct TimeBucket {
+    pub start: SystemTime,
+    pub end: SystemTime,
 }
 
-fn to_attributes(labels: &[LabelPair]) -> AttributeSet {
-    let mut attributes = Vec::new();
-    for label in labels {
-        attributes.push(KeyValue::new(label.get_name().to_string(), label.get_value().to_string()));
+/// Merges the data points of metrics that share the same name, e.g. when they were built for different time buckets.
+/// Metrics without any data points are dropped.
+pub fn merge_metrics(metrics: Vec<Metric>) -> Vec<Metric> {
+    let mut merged: Vec<Metric> = Vec::new();
+    for metric in metrics.into_iter().filter(has_data_points) {
+        let existing = match merged.iter_mut().find(|existing| existing.name == metric.name) {
+            Some(existing) => existing,
+            None => {
+                merged.push(metric);
+                continue;
+            }
+        };
+        match (existing.data.as_mut(), metric.data) {
+            (Some(metric::Data::Sum(existing)), Some(metric::Data::Sum(sum))) => existing.data_points.extend(sum.data_points),
+            (Some(metric::Data::Gauge(existing)), Some(metric::Data::Gauge(gauge))) => existing.data_points.extend(gauge.data_points),
+            (Some(metric::Data::Summary(existing)), Some(metric::Data::Summary(summary))) => existing.data_points.extend(summary.data_points),
+            _ => panic!("metric {} was built with different types", existing.name),
+        }
     }
-    attributes.as_slice().into()
+    merged
 }
 
-fn get_otlp_name_and_unit_from_prom_name(name: &str) -> (String, String) {
-    let mut parts = name.rsplitn(2, '_');
-    let unit = parts.next().unwrap();
-    let otlp_name = parts.next().unwrap();
-    (otlp_name.to_string(), unit.to_string())
+/// Marks every sum as a delta, each data point covering its own bucket from `start_time` to `time`.
+pub fn use_delta_temporality(metrics: &mut [Metric]) {
+    for metric in metrics.iter_mut() {
+        if let Some(metric::Data::Sum(sum)) = metric.data.as_mut() {
+            sum.aggregation_temporality = AggregationTemporality::Delta as i32;
+        }
+    }
 }
 
-fn create_metric_prom(metric_family: &MetricFamily, timestamp: SystemTime) -> Metric {
-    let is_counter = metric_family.get_metric().get(0).map(|metric| metric.has_counter()).unwrap_or(false);
-    let otlp_metric: Metric;
-    if is_counter {
-        let mut data_points = Vec::new();
-        for metric in metric_family.get_metric() {
-            let counter = metric.get_counter();
-            let data_point = DataPoint {
-                attributes: to_attributes(metric.get_label()),
-                start_time: Some(timestamp),
-                time: Some(timestamp),
-                value: counter.get_value(),
-                exemplars: vec![],
-            };
-            data_points.push(data_point);
-        }
-        let sample: opentelemetry_sdk::metrics::data::Sum<f64> = opentelemetry_sdk::metrics::data::Sum {
-            data_points,
-            temporality: Temporality::Cumulative,
-            // See https://opentelemetry.io/docs/specs/otel/compatibility/prometheus_and_openmetrics/#otlp-metric-points-to-prometheus
-            // if the metric is monotonic, then "_total" gets appended to name
-            is_monotonic: false
-        };
-        let (name, unit) = get_otlp_name_and_unit_from_prom_name(metric_family.get_name());
-        otlp_metric = Metric {
-            name: Cow::from(name.to_owned()),
-            description: Cow::from(metric_family.get_help().to_owned()),
-            unit: Unit::new(unit),
-            data: Box::new(sample),
-        }
-    } else {
-        let mut data_points = Vec::new();
-        for metric in metric_family.get_metric() {
-            let gauge = metric.get_gauge();
-            let data_point = DataPoint {
-                attributes: to_attributes(metric.get_label()),
-                start_time: Some(timestamp),
-                time: Some(timestamp),
-                value: gauge.get_value(),
-                exemplars: vec![],
-            };
-            data_points.push(data_point);
+fn has_data_points(metric: &Metric) -> bool {
+    match metric.data.as_ref() {
+        Some(metric::Data::Sum(sum)) => !sum.data_points.is_empty(),
+        Some(metric::Data::Gauge(gauge)) => !gauge.data_points.is_empty(),
+        Some(metric::Data::Summary(summary)) => !summary.data_points.is_empty(),
+        _ => false,
+    }
+}
+
+/// Name, unit and description shared by the metric builders, along with one value per set of label values.
+///
+/// Units are UCUM codes as recommended by the OpenTelemetry semantic conventions, e.g. `ms`, `By` or `{request}`.
+struct Series<V> {
+    name: String,
+    unit: String,
+    description: String,
+    label_names: Vec<String>,
+    values: Vec<(Vec<String>, V)>,
+}
+
+impl<V: Default> Series<V> {
+    fn new(name: &str, unit: &str, description: &str, label_names: &[&str]) -> Series<V> {
+        Series {
+            name: name.to_string(),
+            unit: unit.to_string(),
+            description: description.to_string(),
+            label_names: label_names.iter().map(|label_name| label_name.to_string()).collect(),
+            values: Vec::new(),
         }
-        let sample: opentelemetry_sdk::metrics::data::Gauge<f64> = opentelemetry_sdk::metrics::data::Gauge {
-            data_points
+    }
+
+    fn get_mut(&mut self, label_values: &[&str]) -> &mut V {
+        assert_eq!(label_values.len(), self.label_names.len(), "wrong number of label values for {}", self.name);
+        let index = match self.values.iter().position(|(existing, _)| existing.iter().eq(label_values.iter())) {
+            Some(index) => index,
+            None => {
+                self.values.push((label_values.iter().map(|label_value| label_value.to_string()).collect(), V::default()));
+                self.values.len() - 1
+            }
         };
-        let (name, unit) = get_otlp_name_and_unit_from_prom_name(metric_family.get_name());
-        otlp_metric = Metric {
-            name: Cow::from(name.to_owned()),
-            description: Cow::from(metric_family.get_help().to_owned()),
-            unit: Unit::new(unit),
-            data: Box::new(sample),
+        &mut self.values[index].1
+    }
+
+    /// Empty label values are left out, as for Prometheus an empty label is the same as a missing one.
+    fn attributes(&self, label_values: &[String]) -> Vec<KeyValue> {
+        self.label_names.iter().zip(label_values.iter())
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(name, value)| to_key_value(name, value))
+            .collect()
+    }
+
+    fn to_metric(&self, data: metric::Data) -> Metric {
+        Metric {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            unit: self.unit.clone(),
+            data: Some(data),
         }
     }
-    otlp_metric
+
+    fn number_data_points(&self, bucket: TimeBucket, value: impl Fn(&V) -> f64) -> Vec<NumberDataPoint> {
+        self.values.iter().map(|(label_values, v)| NumberDataPoint {
+            attributes: self.attributes(label_values),
+            start_time_unix_nano: to_unix_nano(bucket.start),
+            time_unix_nano: to_unix_nano(bucket.end),
+            exemplars: vec![],
+            flags: 0,
+            value: Some(number_data_point::Value::AsDouble(value(v))),
+        }).collect()
+    }
+}
+
+/// Values that add up over the bucket, e.g. a number of requests, exported as an OTLP sum.
+pub struct SumVec {
+    series: Series<f64>,
+}
+
+impl SumVec {
+    pub fn new(name: &str, unit: &str, description: &str, label_names: &[&str]) -> SumVec {
+        SumVec { series: Series::new(name, unit, description, label_names) }
+    }
+
+    pub fn inc_by(&mut self, label_values: &[&str], value: f64) {
+        *self.series.get_mut(label_values) += value;
+    }
+
+    pub fn to_metric(&self, bucket: TimeBucket) -> Metric {
+        self.series.to_metric(metric::Data::Sum(Sum {
+            data_points: self.series.number_data_points(bucket, |value| *value),
+            aggregation_temporality: AggregationTemporality::Cumulative as i32,
+            // Counts only ever go up, so Prometheus exposes these as counters with a "_total" suffix, see
+            // https://opentelemetry.io/docs/specs/otel/compatibility/prometheus_and_openmetrics/#otlp-metric-points-to-prometheus
+            is_monotonic: true,
+        }))
+    }
+}
+
+/// Values sampled over the bucket, e.g. an average backlog size, exported as an OTLP gauge.
+pub struct GaugeVec {
+    series: Series<f64>,
+}
+
+impl GaugeVec {
+    pub fn new(name: &str, unit: &str, description: &str, label_names: &[&str]) -> GaugeVec {
+        GaugeVec { series: Series::new(name, unit, description, label_names) }
+    }
+
+    pub fn set(&mut self, label_values: &[&str], value: f64) {
+        *self.series.get_mut(label_values) = value;
+    }
+
+    pub fn to_metric(&self, bucket: TimeBucket) -> Metric {
+        self.series.to_metric(metric::Data::Gauge(Gauge {
+            data_points: self.series.number_data_points(bucket, |value| *value),
+        }))
+    }
+}
+
+#[derive(Default)]
+struct SummaryValue {
+    count: u64,
+    sum: f64,
+    quantiles: Vec<(f64, f64)>,
+}
+
+/// Quantiles pre-computed by the Cloudflare API, exported as an OTLP summary.
+pub struct SummaryVec {
+    series: Series<SummaryValue>,
+}
+
+impl SummaryVec {
+    pub fn new(name: &str, unit: &str, description: &str, label_names: &[&str]) -> SummaryVec {
+        SummaryVec { series: Series::new(name, unit, description, label_names) }
+    }
+
+    /// Records the count, sum and (quantile, value) pairs for the given label values, replacing any previous observation.
+    pub fn observe(&mut self, label_values: &[&str], count: u64, sum: f64, quantiles: &[(f64, f64)]) {
+        *self.series.get_mut(label_values) = SummaryValue { count, sum, quantiles: quantiles.to_vec() };
+    }
+
+    pub fn to_metric(&self, bucket: TimeBucket) -> Metric {
+        let data_points = self.series.values.iter().map(|(label_values, value)| SummaryDataPoint {
+            attributes: self.series.attributes(label_values),
+            start_time_unix_nano: to_unix_nano(bucket.start),
+            time_unix_nano: to_unix_nano(bucket.end),
+            count: value.count,
+            sum: value.sum,
+            quantile_values: value.quantiles.iter().map(|(quantile, value)| summary_data_point::ValueAtQuantile {
+                quantile: *quantile,
+                value: *value,
+            }).collect(),
+            flags: 0,
+        }).collect();
+        self.series.to_metric(metric::Data::Summary(Summary { data_points }))
+    }
+}
+
+pub fn to_unix_nano(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_nanos() as u64
+}
+
+pub fn to_key_value(key: &str, value: &str) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue { value: Some(any_value::Value::StringValue(value.to_string())) }),
+    }
 }
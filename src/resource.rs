@@ -0,0 +1,77 @@
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use worker::Env;
+
+const DEFAULT_SERVICE_NAME: &str = "cloudflare-otlp-exporter";
+
+/// Builds the resource describing the account the metrics were collected for, and this exporter.
+///
+/// Extra attributes can be supplied with the standard `OTEL_RESOURCE_ATTRIBUTES` and `OTEL_SERVICE_NAME`
+/// variables, but `cloud.provider`, `cloud.account.id` and `service.version` are always set by the exporter.
+pub fn build_resource(env: &Env, account_id: &str) -> Resource {
+    let mut attributes: Vec<(String, String)> = vec![
+        ("service.name".to_string(), DEFAULT_SERVICE_NAME.to_string()),
+    ];
+    if let Ok(val) = env.var("OTEL_RESOURCE_ATTRIBUTES") {
+        for (key, value) in parse_resource_attributes(&val.to_string()) {
+            set_attribute(&mut attributes, &key, &value);
+        }
+    }
+    if let Ok(val) = env.var("OTEL_SERVICE_NAME") {
+        set_attribute(&mut attributes, "service.name", &val.to_string());
+    }
+    set_attribute(&mut attributes, "service.version", env!("CARGO_PKG_VERSION"));
+    set_attribute(&mut attributes, "cloud.provider", "cloudflare");
+    set_attribute(&mut attributes, "cloud.account.id", account_id);
+
+    Resource {
+        attributes: attributes.into_iter().map(|(key, value)| KeyValue {
+            key,
+            value: Some(AnyValue { value: Some(any_value::Value::StringValue(value)) }),
+        }).collect(),
+        dropped_attributes_count: 0,
+    }
+}
+
+fn set_attribute(attributes: &mut Vec<(String, String)>, key: &str, value: &str) {
+    match attributes.iter_mut().find(|(existing, _)| existing == key) {
+        Some((_, existing)) => *existing = value.to_string(),
+        None => attributes.push((key.to_string(), value.to_string())),
+    }
+}
+
+/// Parses `key1=value1,key2=value2`, where keys and values may be percent-encoded.
+/// Entries without a key or an `=` are ignored.
+fn parse_resource_attributes(attributes: &str) -> Vec<(String, String)> {
+    let mut parsed = Vec::new();
+    for attribute in attributes.split(',') {
+        let Some((key, value)) = attribute.split_once('=') else {
+            continue;
+        };
+        let key = percent_decode(key.trim());
+        if key.is_empty() {
+            continue;
+        }
+        parsed.push((key, percent_decode(value.trim())));
+    }
+    parsed
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
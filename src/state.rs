@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use opentelemetry_proto::tonic::common::v1::{any_value, KeyValue};
+use opentelemetry_proto::tonic::metrics::v1::{metric, number_data_point, Metric};
+use serde::{Deserialize, Serialize};
+use worker::*;
+use worker::js_sys::{Array, Object, Reflect};
+use worker::wasm_bindgen::JsValue;
+use worker::{wasm_bindgen, wasm_bindgen_futures};
+
+/// Name of the Durable Object binding that keeps the running totals of the sums.
+pub const CUMULATIVE_SUMS_BINDING: &str = "CUMULATIVE_SUMS";
+
+/// Every series is stored under its own key, as a single value is limited to 128 KiB.
+const SERIES_PREFIX: &str = "series/";
+
+/// Maximum number of keys read, written or deleted by a single storage operation.
+const STORAGE_BATCH_SIZE: usize = 128;
+
+/// Series that have not been updated for this long are dropped, and start over from zero if they come back.
+const SERIES_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often the series that are no longer updated are looked for.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The value of a sum data point for a single bucket, or the running total it was replaced with.
+#[derive(Serialize, Deserialize)]
+pub struct SumPoint {
+    series: String,
+    start_time_unix_nano: u64,
+    time_unix_nano: u64,
+    value: f64,
+}
+
+/// Running total of a sum series, used to turn the per-minute values returned by the
+/// Cloudflare API into cumulative sums with a stable start time.
+#[derive(Serialize, Deserialize)]
+struct SeriesState {
+    start_time_unix_nano: u64,
+    last_time_unix_nano: u64,
+    value: f64,
+}
+
+/// Adds the value of every point to the running total of its series, and replaces the point with that total.
+/// Points for buckets that were already accounted for are dropped.
+fn accumulate(series: &mut HashMap<String, SeriesState>, points: Vec<SumPoint>) -> Vec<Option<SumPoint>> {
+    points.into_iter().map(|mut point| {
+        let state = series.entry(point.series.clone()).or_insert(SeriesState {
+            start_time_unix_nano: point.start_time_unix_nano,
+            last_time_unix_nano: point.start_time_unix_nano,
+            value: 0.0,
+        });
+        if point.time_unix_nano <= state.last_time_unix_nano {
+            return None;
+        }
+        state.value += point.value;
+        state.last_time_unix_nano = point.time_unix_nano;
+        point.value = state.value;
+        point.start_time_unix_nano = state.start_time_unix_nano;
+        Some(point)
+    }).collect()
+}
+
+/// Keeps the running totals of the sums of an account. Durable Objects handle one request at a time,
+/// so concurrent runs of the exporter cannot lose each other's updates.
+#[durable_object]
+pub struct CumulativeSums {
+    state: State,
+}
+
+#[durable_object]
+impl DurableObject for CumulativeSums {
+    fn new(state: State, _env: Env) -> Self {
+        Self { state }
+    }
+
+    async fn fetch(&mut self, mut req: Request) -> Result<Response> {
+        let points: Vec<SumPoint> = req.json().await?;
+        let mut storage = self.state.storage();
+        let mut keys: Vec<String> = points.iter().map(|point| point.series.clone()).collect();
+        keys.sort();
+        keys.dedup();
+
+        // Stored as JSON strings, as nanosecond timestamps do not fit in a JavaScript number
+        let mut series = HashMap::new();
+        for chunk in keys.chunks(STORAGE_BATCH_SIZE) {
+            let values = storage.get_multiple(chunk.iter().map(|key| storage_key(key)).collect()).await?;
+            for key in chunk.iter() {
+                if let Some(json) = values.get(&JsValue::from_str(&storage_key(key))).as_string() {
+                    series.insert(key.clone(), serde_json::from_str::<SeriesState>(&json)?);
+                }
+            }
+        }
+
+        let totals = accumulate(&mut series, points);
+
+        let series: Vec<(String, SeriesState)> = series.into_iter().collect();
+        for chunk in series.chunks(STORAGE_BATCH_SIZE) {
+            let values = Object::new();
+            for (key, state) in chunk.iter() {
+                Reflect::set(&values, &JsValue::from_str(&storage_key(key)), &JsValue::from_str(&serde_json::to_string(state)?))?;
+            }
+            storage.put_multiple_raw(values).await?;
+        }
+        if storage.get_alarm().await?.is_none() {
+            storage.set_alarm(PRUNE_INTERVAL).await?;
+        }
+        Response::from_json(&totals)
+    }
+
+    /// Drops the series that have not been updated for a while.
+    async fn alarm(&mut self) -> Result<Response> {
+        let mut storage = self.state.storage();
+        let oldest = (Date::now().as_millis() * 1_000_000).saturating_sub(SERIES_RETENTION.as_nanos() as u64);
+        let values = storage.list_with_options(ListOptions::new().prefix(SERIES_PREFIX)).await?;
+        let mut stale = Vec::new();
+        for entry in values.entries() {
+            let entry: Array = entry?.into();
+            let key = entry.get(0).as_string().unwrap_or_default();
+            let json = entry.get(1).as_string().unwrap_or_default();
+            match serde_json::from_str::<SeriesState>(&json) {
+                Ok(state) if state.last_time_unix_nano >= oldest => continue,
+                _ => stale.push(key),
+            }
+        }
+        for chunk in stale.chunks(STORAGE_BATCH_SIZE) {
+            storage.delete_multiple(chunk.to_vec()).await?;
+        }
+        if stale.len() < values.size() as usize {
+            storage.set_alarm(PRUNE_INTERVAL).await?;
+        }
+        Response::empty()
+    }
+}
+
+fn storage_key(series: &str) -> String {
+    format!("{}{}", SERIES_PREFIX, series)
+}
+
+/// Replaces the sum data points of an account with their running totals, kept by the `CumulativeSums` Durable Object.
+pub async fn accumulate_sums(namespace: &ObjectNamespace, account_id: &str, metrics: &mut [Metric]) -> Result<()> {
+    let points = sum_points(metrics);
+    let mut init = RequestInit::new();
+    init.method = Method::Post;
+    init.with_body(Some(JsValue::from_str(&serde_json::to_string(&points)?)));
+    let stub = namespace.id_from_name(account_id)?.get_stub()?;
+    let mut res = stub.fetch_with_request(Request::new_with_init("https://cumulative-sums/accumulate", &init)?).await?;
+    if res.status_code() != 200 {
+        return Err(Error::RustError(res.text().await?));
+    }
+    let mut totals = res.json::<Vec<Option<SumPoint>>>().await?.into_iter();
+    for metric in metrics.iter_mut() {
+        let Some(metric::Data::Sum(sum)) = metric.data.as_mut() else {
+            continue;
+        };
+        sum.data_points.retain_mut(|data_point| match totals.next().flatten() {
+            Some(total) => {
+                data_point.value = Some(number_data_point::Value::AsDouble(total.value));
+                data_point.start_time_unix_nano = total.start_time_unix_nano;
+                true
+            },
+            None => false,
+        });
+    }
+    Ok(())
+}
+
+fn sum_points(metrics: &[Metric]) -> Vec<SumPoint> {
+    let mut points = Vec::new();
+    for metric in metrics.iter() {
+        let Some(metric::Data::Sum(sum)) = metric.data.as_ref() else {
+            continue;
+        };
+        for data_point in sum.data_points.iter() {
+            points.push(SumPoint {
+                series: series_key(&metric.name, &data_point.attributes),
+                start_time_unix_nano: data_point.start_time_unix_nano,
+                time_unix_nano: data_point.time_unix_nano,
+                value: match data_point.value {
+                    Some(number_data_point::Value::AsDouble(value)) => value,
+                    Some(number_data_point::Value::AsInt(value)) => value as f64,
+                    None => 0.0,
+                },
+            });
+        }
+    }
+    points
+}
+
+fn series_key(name: &str, attributes: &[KeyValue]) -> String {
+    let mut key = name.to_string();
+    for attribute in attributes.iter() {
+        let value = match attribute.value.as_ref().and_then(|value| value.value.as_ref()) {
+            Some(any_value::Value::StringValue(value)) => value.clone(),
+            Some(value) => format!("{:?}", value),
+            None => String::new(),
+        };
+        key.push_str(&format!(",{}={}", attribute.key, value));
+    }
+    key
+}
@@ -0,0 +1,76 @@
+use opentelemetry_proto::tonic::metrics::v1::Metric;
+use crate::metrics::{GaugeVec, TimeBucket};
+
+/// Overall outcome of a trigger across all collectors.
+#[derive(Debug, PartialEq)]
+pub enum RunStatus {
+    Success,
+    PartialSuccess,
+    Failure,
+}
+
+impl RunStatus {
+    /// Overall outcome across several accounts: a failure in one account only makes the run a partial success.
+    pub fn combine(statuses: &[RunStatus]) -> RunStatus {
+        if statuses.iter().all(|status| *status == RunStatus::Success) {
+            RunStatus::Success
+        } else if statuses.iter().all(|status| *status == RunStatus::Failure) {
+            RunStatus::Failure
+        } else {
+            RunStatus::PartialSuccess
+        }
+    }
+}
+
+/// Tracks which collectors succeeded or failed during a trigger, and exposes this as self-telemetry.
+#[derive(Default)]
+pub struct RunReport {
+    succeeded: Vec<String>,
+    failed: Vec<String>,
+    truncated: Vec<String>,
+}
+
+impl RunReport {
+    pub fn record_success(&mut self, collector: &str) {
+        self.succeeded.push(collector.to_string());
+    }
+
+    /// Records that the collector succeeded, but could not fetch every group in the window.
+    pub fn record_truncated(&mut self, collector: &str) {
+        self.truncated.push(collector.to_string());
+    }
+
+    pub fn record_failure(&mut self, collector: &str) {
+        self.failed.push(collector.to_string());
+    }
+
+    pub fn status(&self) -> RunStatus {
+        if self.failed.is_empty() {
+            RunStatus::Success
+        } else if self.succeeded.is_empty() {
+            RunStatus::Failure
+        } else {
+            RunStatus::PartialSuccess
+        }
+    }
+
+    pub fn to_metrics(&self, bucket: TimeBucket) -> Vec<Metric> {
+        let mut collector_success = GaugeVec::new("cloudflare_exporter_collector_success", "1", "Whether the last run of the collector succeeded (1) or failed (0)", &["collector"]);
+
+        let mut truncated_results = GaugeVec::new("cloudflare_exporter_truncated_results", "1", "Whether the last run of the collector had more results than it could fetch (1) or not (0)", &["collector"]);
+
+        for collector in self.succeeded.iter() {
+            collector_success.set(&[collector.as_str()], 1.0);
+            let truncated = if self.truncated.contains(collector) { 1.0 } else { 0.0 };
+            truncated_results.set(&[collector.as_str()], truncated);
+        }
+        for collector in self.failed.iter() {
+            collector_success.set(&[collector.as_str()], 0.0);
+        }
+
+        vec![
+            collector_success.to_metric(bucket),
+            truncated_results.to_metric(bucket),
+        ]
+    }
+}